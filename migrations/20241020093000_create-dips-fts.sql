@@ -0,0 +1,61 @@
+-- Add migration script here
+CREATE VIRTUAL TABLE dips_fts USING fts5(
+    dip_id UNINDEXED,
+    value,
+    note,
+    tags
+);
+
+INSERT INTO dips_fts (dip_id, value, note, tags)
+SELECT dips.id,
+    dips.value,
+    COALESCE(dips.note, ''),
+    COALESCE((
+        SELECT GROUP_CONCAT(tags.name, ' ') FROM dips_tags
+        JOIN tags ON dips_tags.tag_id = tags.id
+        WHERE dips_tags.dip_id = dips.id
+    ), '')
+FROM dips;
+
+--
+CREATE TRIGGER dips_fts_after_insert AFTER INSERT ON dips BEGIN
+    INSERT INTO dips_fts (dip_id, value, note, tags)
+    VALUES (new.id, new.value, COALESCE(new.note, ''), '');
+END;
+
+CREATE TRIGGER dips_fts_after_update AFTER UPDATE OF value, note ON dips BEGIN
+    UPDATE dips_fts SET value = new.value, note = COALESCE(new.note, '')
+    WHERE dip_id = old.id;
+END;
+
+CREATE TRIGGER dips_fts_after_delete AFTER DELETE ON dips BEGIN
+    DELETE FROM dips_fts WHERE dip_id = old.id;
+END;
+
+--
+CREATE TRIGGER dips_tags_fts_after_insert AFTER INSERT ON dips_tags BEGIN
+    UPDATE dips_fts SET tags = COALESCE((
+        SELECT GROUP_CONCAT(tags.name, ' ') FROM dips_tags
+        JOIN tags ON dips_tags.tag_id = tags.id
+        WHERE dips_tags.dip_id = new.dip_id
+    ), '')
+    WHERE dip_id = new.dip_id;
+END;
+
+CREATE TRIGGER dips_tags_fts_after_delete AFTER DELETE ON dips_tags BEGIN
+    UPDATE dips_fts SET tags = COALESCE((
+        SELECT GROUP_CONCAT(tags.name, ' ') FROM dips_tags
+        JOIN tags ON dips_tags.tag_id = tags.id
+        WHERE dips_tags.dip_id = old.dip_id
+    ), '')
+    WHERE dip_id = old.dip_id;
+END;
+
+CREATE TRIGGER tags_fts_after_update AFTER UPDATE OF name ON tags BEGIN
+    UPDATE dips_fts SET tags = COALESCE((
+        SELECT GROUP_CONCAT(tags.name, ' ') FROM dips_tags
+        JOIN tags ON dips_tags.tag_id = tags.id
+        WHERE dips_tags.dip_id = dips_fts.dip_id
+    ), '')
+    WHERE dip_id IN (SELECT dip_id FROM dips_tags WHERE tag_id = new.id);
+END;
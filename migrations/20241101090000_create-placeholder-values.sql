@@ -0,0 +1,11 @@
+-- The last value used for every template placeholder, per scope.
+-- The global dips have no dir context, so the dir_context_id is null.
+CREATE TABLE placeholder_values (
+    dir_context_id TEXT,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (dir_context_id) REFERENCES dir_contexts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX placeholder_values_scope ON placeholder_values (dir_context_id, name);
@@ -0,0 +1,6 @@
+-- The remote in the `host/owner/repo` form, so the SSH and HTTPS URLs of the
+-- same repository match. The existing rows are filled in by the application
+-- right after the migrations, as SQLite can't parse the URLs.
+ALTER TABLE dir_contexts ADD COLUMN canonical_remote TEXT;
+
+CREATE INDEX dir_contexts_canonical_remote ON dir_contexts (canonical_remote);
@@ -0,0 +1,4 @@
+-- The path of the scope inside of its git repository, empty at the root, so
+-- the scopes of a monorepo match in every clone of it. The existing scopes
+-- keep matching the whole repository, as they did so far.
+ALTER TABLE dir_contexts ADD COLUMN subpath TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,5 @@
+-- The branch a dip is attached to, it only shows while the branch is checked
+-- out. The dips of the deleted branches are archived by `dips branch-gc`, the
+-- archived ones don't show anywhere.
+ALTER TABLE dips ADD COLUMN branch TEXT;
+ALTER TABLE dips ADD COLUMN archived_at TIMESTAMP;
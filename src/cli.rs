@@ -1,10 +1,24 @@
+use crate::clipboard;
 use crate::commands;
-use crate::configuration::{Application, ConfigError, Environment, Settings};
-use clap::{Parser, Subcommand};
+use crate::commands::completions::{self, CompletionShell};
+use crate::commands::export::ExportFormat;
+use crate::commands::get::OutputFormat;
+use crate::commands::scope::RemoveMode;
+use crate::commands::shell_init::InitShell;
+use crate::configuration::{Application, Environment, Settings};
+use crate::error::DipsError;
+use crate::models::backup::ImportMode;
+use crate::models::tag_expr::TagExpr;
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::{ArgValueCandidates, CompleteEnv};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
+    /// Act on the scope of the directory instead of the current one
+    #[arg(short = 'C', long = "dir", value_name = "PATH", global = true)]
+    dir: Option<PathBuf>,
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -14,47 +28,420 @@ enum Commands {
     Init,
     Add {
         input: String,
-        #[arg(short = 't', long)]
-        tag: Option<String>,
+        /// Describe what the dip does or when to use it
+        #[arg(short, long)]
+        note: Option<String>,
+        /// Tag the dip, repeat it or use a comma separated list for more tags
+        #[arg(short = 't', long = "tag", add = ArgValueCandidates::new(completions::tags))]
+        tags: Vec<String>,
         #[arg(short, long)]
         global: bool,
+        /// Only show the dip while the branch is checked out, the current one
+        /// by default
+        #[arg(
+            long,
+            value_name = "NAME",
+            num_args = 0..=1,
+            require_equals = true,
+            conflicts_with = "global"
+        )]
+        branch: Option<Option<String>>,
+        /// Add to a scope of this very directory, e.g. a package of a
+        /// monorepo, instead of the closest scope of the git repository
+        #[arg(long, conflicts_with = "global")]
+        here: bool,
     },
     Get {
         #[clap(short, long)]
         all: bool,
+        /// The output format, `plain` unless it's set in the config
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+        /// How many inherited scopes to show after the closest one
+        #[arg(short, long)]
+        depth: Option<usize>,
+        /// Only show the dips matching the tag expression, e.g. `db and (prod or staging)`
+        /// or `!legacy`. Repeated filters all have to match.
+        #[arg(
+            short = 't',
+            long = "tags",
+            visible_alias = "tag",
+            add = ArgValueCandidates::new(completions::tags)
+        )]
+        tags: Vec<TagExpr>,
+        /// Copy the value of the selected dip to the clipboard instead
+        #[arg(
+            long,
+            value_name = "SELECTOR",
+            conflicts_with_all = ["all", "format", "depth", "tags"],
+            add = ArgValueCandidates::new(completions::selectors)
+        )]
+        copy: Option<String>,
+    },
+    /// Full text search over the values, notes and tags
+    Search {
+        #[arg(required = true)]
+        query: Vec<String>,
+        #[clap(short, long)]
+        all: bool,
+    },
+    /// Export the dips with their scopes and tags to stdout
+    Export {
+        #[arg(short, long, value_enum, default_value_t)]
+        format: ExportFormat,
+        /// Export the scope of this directory instead of the closest one
+        #[arg(long, conflicts_with = "all")]
+        scope: Option<PathBuf>,
+        /// Export the whole database
+        #[clap(short, long)]
+        all: bool,
+        /// Write the dips of this repository into the shared repository file
+        #[arg(long, conflicts_with_all = ["format", "scope", "all"])]
+        to_repo: bool,
+    },
+    /// Import the dips exported as json or yaml
+    Import {
+        #[arg(required_unless_present = "from_repo")]
+        file: Option<PathBuf>,
+        /// Keep the existing dips and only add the missing ones, the default
+        #[arg(long, conflicts_with = "replace")]
+        merge: bool,
+        /// Remove the existing dips of the imported scopes first, all of them
+        /// for a backup of every scope
+        #[arg(long)]
+        replace: bool,
+        /// Show what would be imported without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Copy the dips from the shared repository file into the local database
+        #[arg(long, conflicts_with_all = ["file", "merge", "replace", "dry_run"])]
+        from_repo: bool,
+    },
+    /// Remove a dip by id prefix, exact value or fuzzy query
+    Rm {
+        #[arg(add = ArgValueCandidates::new(completions::selectors))]
+        selector: String,
+        /// Don't ask before removing a dip that only matches fuzzily
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Run a dip with your shell in the directory of its scope
+    ///
+    /// Exits with the exit code of the command, which can overlap with the
+    /// codes dips uses for its own errors.
+    Run {
+        #[arg(add = ArgValueCandidates::new(completions::selectors))]
+        selector: String,
+        /// Don't ask before running the commands that look destructive
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Add or remove the tags of a dip, e.g. `dips tag <selector> +foo -bar`
+    Tag {
+        #[arg(add = ArgValueCandidates::new(completions::selectors))]
+        selector: String,
+        #[arg(required = true, allow_hyphen_values = true)]
+        changes: Vec<String>,
+    },
+    /// List and clean up the tags
+    Tags {
+        #[command(subcommand)]
+        command: TagsCommands,
+    },
+    /// Edit the note of a dip in $EDITOR
+    Note {
+        #[arg(add = ArgValueCandidates::new(completions::selectors))]
+        selector: String,
+    },
+    /// Change the value or the note of a dip
+    Edit {
+        #[arg(add = ArgValueCandidates::new(completions::selectors))]
+        selector: String,
+        #[arg(long)]
+        value: Option<String>,
+        /// The new note, an empty one removes it
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Print the script that shows the dips of a scope when you enter it,
+    /// e.g. `eval "$(dips shell-init bash)"`
+    ShellInit {
+        #[arg(value_enum)]
+        shell: InitShell,
+    },
+    /// Print the completion script, e.g. `source <(dips completions bash)`
+    Completions {
+        #[arg(value_enum)]
+        shell: CompletionShell,
+    },
+    /// Print the man page, or write the pages of all the commands to a directory
+    Man {
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+    /// Manage the scopes, the directories the dips are added to
+    Scope {
+        #[command(subcommand)]
+        command: ScopeCommands,
+    },
+    /// Archive the dips of the branches deleted from the git repository
+    BranchGc,
+    /// Read or change the settings in the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Print the summary of the closest scope, called by the shell-init script
+    #[command(hide = true)]
+    Hook {
+        /// Id of the shell session, so the same scope isn't printed repeatedly
+        #[arg(long)]
+        session: Option<String>,
+    },
+    /// Hold the copied text on the system clipboard until it's replaced
+    #[command(hide = true)]
+    ClipboardServe,
+}
+
+#[derive(Subcommand)]
+enum ScopeCommands {
+    /// List the scopes with the number of their dips
+    Ls,
+    /// Print the details and the dips of a scope, the closest one by default
+    Show {
+        #[arg(add = ArgValueCandidates::new(completions::scopes))]
+        path: Option<PathBuf>,
+    },
+    /// Move a scope to another path, e.g. when the repository was moved on disk
+    Mv {
+        #[arg(add = ArgValueCandidates::new(completions::scopes))]
+        old: PathBuf,
+        new: PathBuf,
+    },
+    /// Remove a scope, the dips in it need --cascade or --reassign-global
+    Rm {
+        #[arg(add = ArgValueCandidates::new(completions::scopes))]
+        path: PathBuf,
+        /// Remove the dips of the scope too
+        #[arg(long, conflicts_with = "reassign_global")]
+        cascade: bool,
+        /// Keep the dips of the scope as the global ones
+        #[arg(long)]
+        reassign_global: bool,
+    },
+    /// Remove the scopes without any dips whose directory no longer exists
+    Prune,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the effective settings, or the value of a key like `tui.theme`
+    Get { key: Option<String> },
+    /// Set a key in the config file, e.g. `dips config set format json`
+    Set { key: String, value: String },
+    /// Print the path of the config file
+    Path,
+}
+
+#[derive(Subcommand)]
+enum TagsCommands {
+    /// List the tags with the number of dips in every scope
+    Ls,
+    /// Rename a tag
+    Rename {
+        #[arg(add = ArgValueCandidates::new(completions::tags))]
+        old: String,
+        new: String,
     },
+    /// Move the dips of the first tag to the second one and remove the first
+    Merge {
+        #[arg(add = ArgValueCandidates::new(completions::tags))]
+        from: String,
+        #[arg(add = ArgValueCandidates::new(completions::tags))]
+        into: String,
+    },
+    /// Remove a tag from all the dips
+    Rm {
+        #[arg(add = ArgValueCandidates::new(completions::tags))]
+        name: String,
+    },
+    /// Remove the tags without any dips
+    Prune,
 }
 
-pub async fn run() {
+/// Run the command and return its error, `main` turns it into the exit code.
+pub async fn run() -> Result<(), DipsError> {
+    // The completion scripts call us back with the words to complete.
+    CompleteEnv::with_factory(Cli::command)
+        .var(completions::COMPLETE_VAR)
+        .complete();
+
     let cli = Cli::parse();
-    let settings = Settings::build(&Environment::current());
+    let mut settings = Settings::build(&Environment::current());
+    settings.dir = cli.dir;
 
     match cli.command {
-        Some(Commands::Init) => {
-            commands::init::init(settings).await;
+        Some(Commands::Init) => commands::init::init(settings).await,
+        Some(Commands::ShellInit { shell }) => {
+            commands::shell_init::exec(shell);
+            Ok(())
+        }
+        Some(Commands::Completions { shell }) => {
+            completions::exec(&Cli::command(), shell);
+            Ok(())
+        }
+        Some(Commands::Man { out_dir }) => {
+            completions::man(Cli::command(), out_dir.as_deref());
+            Ok(())
+        }
+        Some(Commands::Config { command }) => match command {
+            ConfigCommands::Get { key } => commands::config::get(key.as_deref()),
+            ConfigCommands::Set { key, value } => commands::config::set(&key, &value),
+            ConfigCommands::Path => {
+                commands::config::path();
+                Ok(())
+            }
+        },
+        Some(Commands::ClipboardServe) => Ok(clipboard::serve()?),
+        Some(Commands::Hook { session }) => {
+            // The hook runs on every directory change, so it skips the
+            // migrations and stays quiet when dips is not initialized.
+            if let Ok(app) = Application::connect(settings).await {
+                commands::hook::exec(&app, session.as_deref()).await;
+            }
+            Ok(())
         }
         _ => {
-            let app = match Application::build(settings).await {
-                Ok(app) => app,
-                Err(e) => match e {
-                    ConfigError::Uninitialized => {
-                        println!("Dips is not initialized. Please run `dips init`");
-                        std::process::exit(0);
-                    }
-                },
-            };
+            let app = Application::build(settings).await?;
 
             match cli.command {
-                Some(Commands::Add { input, tag, global }) => {
-                    commands::add::add(&app, &input, tag.as_deref(), global).await;
+                Some(Commands::Add {
+                    input,
+                    note,
+                    tags,
+                    global,
+                    branch,
+                    here,
+                }) => {
+                    let branch = match branch {
+                        Some(name) => Some(commands::add::branch_name(&app, name)?),
+                        None => None,
+                    };
+                    let options = commands::add::AddOptions::new()
+                        .with_global(global)
+                        .with_branch(branch.as_deref())
+                        .with_here(here);
+                    commands::add::add(&app, &input, note.as_deref(), &tags, &options).await
+                }
+                Some(Commands::Get {
+                    all,
+                    format,
+                    depth,
+                    tags,
+                    copy,
+                }) => match copy {
+                    Some(selector) => commands::get::copy(&app, &selector).await,
+                    None => {
+                        let format = format.unwrap_or(app.settings.user.format);
+                        let depth = depth.or(app.settings.user.depth);
+                        commands::get::exec(&app, all, format, depth, tags).await
+                    }
+                },
+                Some(Commands::Search { query, all }) => {
+                    commands::search::exec(&app, &query.join(" "), all).await
+                }
+                Some(Commands::Export {
+                    format,
+                    scope,
+                    all,
+                    to_repo,
+                }) => commands::export::exec(&app, format, scope.as_deref(), all, to_repo).await,
+                Some(Commands::Import {
+                    file,
+                    merge: _,
+                    replace,
+                    dry_run,
+                    from_repo,
+                }) => {
+                    let mode = if replace {
+                        ImportMode::Replace
+                    } else {
+                        ImportMode::Merge
+                    };
+                    commands::import::exec(&app, file.as_deref(), mode, dry_run, from_repo).await
+                }
+                Some(Commands::Rm { selector, yes }) => {
+                    commands::rm::exec(&app, &selector, yes).await
+                }
+                Some(Commands::Run { selector, yes }) => {
+                    let status = commands::run::exec(&app, &selector, yes).await?;
+                    // Exit with the code of the command, so it can be used in scripts.
+                    match commands::run::exit_code(&status) {
+                        0 => Ok(()),
+                        code => std::process::exit(code),
+                    }
+                }
+                Some(Commands::Tag { selector, changes }) => {
+                    commands::tag::exec(&app, &selector, &changes).await
                 }
-                Some(Commands::Get { all }) => {
-                    commands::get::exec(&app, all).await;
+                Some(Commands::Tags { command }) => match command {
+                    TagsCommands::Ls => commands::tags::list(&app).await,
+                    TagsCommands::Rename { old, new } => {
+                        commands::tags::rename(&app, &old, &new).await
+                    }
+                    TagsCommands::Merge { from, into } => {
+                        commands::tags::merge(&app, &from, &into).await
+                    }
+                    TagsCommands::Rm { name } => commands::tags::rm(&app, &name).await,
+                    TagsCommands::Prune => commands::tags::prune(&app).await,
+                },
+                Some(Commands::Scope { command }) => match command {
+                    ScopeCommands::Ls => commands::scope::list(&app).await,
+                    ScopeCommands::Show { path } => {
+                        commands::scope::show(&app, path.as_deref()).await
+                    }
+                    ScopeCommands::Mv { old, new } => commands::scope::mv(&app, &old, &new).await,
+                    ScopeCommands::Rm {
+                        path,
+                        cascade,
+                        reassign_global,
+                    } => {
+                        let mode = if cascade {
+                            RemoveMode::Cascade
+                        } else if reassign_global {
+                            RemoveMode::ReassignGlobal
+                        } else {
+                            RemoveMode::Empty
+                        };
+                        commands::scope::rm(&app, &path, mode).await
+                    }
+                    ScopeCommands::Prune => commands::scope::prune(&app).await,
+                },
+                Some(Commands::BranchGc) => commands::branch_gc::exec(&app).await,
+                Some(Commands::Note { selector }) => commands::note::exec(&app, &selector).await,
+                Some(Commands::Edit {
+                    selector,
+                    value,
+                    note,
+                }) => {
+                    commands::edit::exec(&app, &selector, value.as_deref(), note.as_deref()).await
                 }
                 _ => commands::core::exec(app)
                     .await
-                    .expect("Failed to run the app"),
+                    .map_err(|e| std::io::Error::other(e.to_string()).into()),
             }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cli_definition_is_valid() {
+        // Catches the typos in the argument names used by conflicts and the
+        // completions before anybody runs the command.
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,121 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::io::{Read, Write};
+
+/// Where the copied text goes, the `clipboard` in the config.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardBackend {
+    /// The system clipboard, or the terminal when there is no display server.
+    #[default]
+    Auto,
+    System,
+    /// Only the OSC 52 escape sequence of the terminal.
+    Osc52,
+}
+
+/// How the text made it to the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CopyMethod {
+    System,
+    Terminal,
+}
+
+/// The OSC 52 escape sequence asks the terminal to set the clipboard, which
+/// also works over SSH. Inside tmux it has to be wrapped in a passthrough.
+pub fn osc52(text: &str) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+fn has_display() -> bool {
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        return true;
+    }
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|x| std::env::var_os(x).is_some_and(|x| !x.is_empty()))
+}
+
+/// The copy methods to try in order for the configured backend.
+pub fn methods(backend: ClipboardBackend, has_display: bool) -> &'static [CopyMethod] {
+    match backend {
+        ClipboardBackend::Auto if has_display => &[CopyMethod::System, CopyMethod::Terminal],
+        ClipboardBackend::Auto | ClipboardBackend::Osc52 => &[CopyMethod::Terminal],
+        ClipboardBackend::System => &[CopyMethod::System],
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn copy_system(text: &str) -> std::io::Result<()> {
+    use std::process::{Command, Stdio};
+
+    // On X11 and Wayland the contents are served by the process that set
+    // them and go away when it exits, so a detached `dips clipboard-serve`
+    // keeps them until something else is copied.
+    arboard::Clipboard::new().map_err(std::io::Error::other)?;
+    let mut child = Command::new(std::env::current_exe()?)
+        .arg("clipboard-serve")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn copy_system(text: &str) -> std::io::Result<()> {
+    arboard::Clipboard::new()
+        .and_then(|mut x| x.set_text(text))
+        .map_err(std::io::Error::other)
+}
+
+/// Set the system clipboard to the text read from stdin and keep serving it
+/// until another program replaces it.
+pub fn serve() -> std::io::Result<()> {
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+    let mut clipboard = arboard::Clipboard::new().map_err(std::io::Error::other)?;
+    #[cfg(target_os = "linux")]
+    let result = {
+        use arboard::SetExtLinux;
+        clipboard.set().wait().text(text)
+    };
+    #[cfg(not(target_os = "linux"))]
+    let result = clipboard.set_text(text);
+    result.map_err(std::io::Error::other)
+}
+
+fn copy_terminal(text: &str) -> std::io::Result<()> {
+    // Write to the terminal itself, so it works even when stdout is piped.
+    let mut out: Box<dyn Write> = match std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(tty) => Box::new(tty),
+        Err(_) => Box::new(std::io::stdout()),
+    };
+    out.write_all(osc52(text).as_bytes())?;
+    out.flush()
+}
+
+/// Copy the text to the clipboard. Without a display server, e.g. over SSH
+/// or on a headless machine, the automatic backend falls back to the terminal.
+pub fn copy(text: &str, backend: ClipboardBackend) -> std::io::Result<CopyMethod> {
+    let mut result = Err(std::io::Error::other("No clipboard available"));
+    for method in methods(backend, has_display()) {
+        result = match method {
+            CopyMethod::System => copy_system(text),
+            CopyMethod::Terminal => copy_terminal(text),
+        }
+        .map(|_| *method);
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
@@ -1,92 +1,180 @@
 use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::dip;
+use crate::models::dir_context::{self, DirContext};
 use crate::models::tag;
-use crate::models::{dip, dir_context};
-
-async fn value_exists(app: &Application, value: &str, global: bool) -> bool {
-    // - if dips.value is equal $1 -> additional check
-    // additional check for equal value:
-    //      - dips.dir_context_id
-    //          - if null and is_global variable true -> might be match otherwise not
-    //          - if string and dir_path, git_remote, git_dir_name matches dir_context ref -> might be
-    //          match otherwise not
-    //      - dips.context_group_id
-    //          - if null and group is null -> might be a match
-    //          - if string and group value equals to context_group name -> might be match
-    let path = app.context_dir.path();
-    let git_remote = app.context_dir.git_remote();
-    let git_dir = app.context_dir.git_dir();
-    sqlx::query!(
-        r"
-            select d.id from dips d
-            left join dir_contexts c on d.dir_context_id = c.id
-            where d.value = $1
-              and (
-                  (d.dir_context_id IS NOT NULL and (c.dir_path = $2 or c.git_remote = $3 or c.git_dir_name = $4))
-                  or 
-                  (d.dir_context_id IS NULL and $5)
-              )
-        ",
-        value,
-        path,
-        git_remote,
-        git_dir,
-        global,
+
+/// The existing scope the dip would be added to. Inside of a git repository
+/// it's the closest scope of the repository, so the subdirectories don't get
+/// a scope each, unless `here` asks for the directory itself.
+async fn find_scope(app: &Application, here: bool) -> Result<Option<DirContext>, DipsError> {
+    let ctx = &app.context_dir;
+    if !here {
+        if let Some(scope) = dir_context::get_closest_in_repo(&app.db_pool, ctx).await? {
+            return Ok(Some(scope));
+        }
+    }
+    let scope = dir_context::db_find_one(
+        &app.db_pool,
+        &ctx.path(),
+        &ctx.subpath(),
+        ctx.git_dir(),
+        ctx.git_remote(),
+        ctx.match_dir_name(),
     )
-    .fetch_optional(&app.db_pool)
-    .await
-    .expect("Failed to execute query")
-    .is_some()
+    .await?;
+    Ok(scope)
 }
 
-async fn add_global(app: &Application, value: &str, _tag: Option<&str>) {
-    let mut tx = app
-        .db_pool
-        .begin()
-        .await
-        .expect("Failed to start transaction in sqlite");
+/// Check if the value is already in the global scope, or in the scope the
+/// value would be added to.
+pub async fn value_exists(
+    app: &Application,
+    value: &str,
+    options: &AddOptions,
+) -> Result<bool, DipsError> {
+    let scope_id = if options.global {
+        None
+    } else {
+        match find_scope(app, options.here).await? {
+            Some(scope) => Some(scope.id),
+            None => return Ok(false),
+        }
+    };
+    let exists = dip::value_exists(&app.db_pool, value, scope_id.as_ref()).await?;
+    Ok(exists)
+}
+
+async fn add_global(
+    app: &Application,
+    value: &str,
+    note: Option<&str>,
+    tags: &[String],
+) -> Result<(), DipsError> {
+    let mut tx = app.db_pool.begin().await?;
 
-    dip::create_with_transaction(&mut tx, None, &value, None)
-        .await
-        .expect("Failed to create a dip");
+    let item = dip::create_with_transaction(&mut tx, None, value, note).await?;
+
+    for tag in tags {
+        tag::create_dip_tag(&mut tx, &item.id, tag).await?;
+    }
 
     // Commit the transaction
-    tx.commit().await.expect("Failed to commit transaction");
+    tx.commit().await?;
+    Ok(())
 }
 
-async fn add_contextual(app: &Application, value: &str, tag: Option<&str>) {
-    let mut tx = app
-        .db_pool
-        .begin()
-        .await
-        .expect("Failed to start transaction in sqlite");
-    let current_dir_context = dir_context::get_or_create_current(&mut tx)
-        .await
-        .expect("Failed to get the current dir context");
-
-    let item = dip::create_with_transaction(&mut tx, Some(current_dir_context.id), &value, None)
-        .await
-        .expect("Failed to create a dip");
-
-    if let Some(tag) = tag {
-        tag::create_dip_tag(&mut tx, &item.id, tag)
-            .await
-            .expect("Failed to tag a dip");
+async fn add_contextual(
+    app: &Application,
+    value: &str,
+    note: Option<&str>,
+    tags: &[String],
+    branch: Option<&str>,
+    here: bool,
+) -> Result<(), DipsError> {
+    let scope = find_scope(app, here).await?;
+    let mut tx = app.db_pool.begin().await?;
+    let current_dir_context = match scope {
+        Some(scope) => scope,
+        None => dir_context::get_or_create_current(&mut tx, &app.context_dir).await?,
+    };
+
+    let item =
+        dip::create_with_transaction(&mut tx, Some(current_dir_context.id), value, note).await?;
+    if let Some(branch) = branch {
+        dip::set_branch(&mut tx, &item.id, branch).await?;
+    }
+
+    for tag in tags {
+        tag::create_dip_tag(&mut tx, &item.id, tag).await?;
     }
 
     // Commit the transaction
-    tx.commit().await.expect("Failed to commit transaction");
+    tx.commit().await?;
+    Ok(())
 }
 
-pub async fn add(app: &Application, value: &str, tags: Option<&str>, global: bool) {
-    if value_exists(app, value, global).await {
-        println!("{value} is already added in this context.");
-    } else {
-        if global {
-            add_global(app, value, tags).await;
-        } else {
-            add_contextual(app, value, tags).await;
+/// Where `add` puts the dip: the global scope, a branch, or the scope of the
+/// directory itself instead of the closest one of the repository.
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    global: bool,
+    branch: Option<String>,
+    here: bool,
+}
+
+impl AddOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_global(self, global: bool) -> Self {
+        Self { global, ..self }
+    }
+
+    /// The dip attached to the branch only shows while the branch is checked
+    /// out.
+    pub fn with_branch(self, branch: Option<&str>) -> Self {
+        Self {
+            branch: branch.map(str::to_string),
+            ..self
         }
+    }
+
+    /// Use the scope of the directory itself, e.g. a package of a monorepo.
+    pub fn with_here(self, here: bool) -> Self {
+        Self { here, ..self }
+    }
+}
+
+/// Add the value to the current scope, or to the global one. The tags can be
+/// repeated or given as a comma separated list.
+pub async fn add(
+    app: &Application,
+    value: &str,
+    note: Option<&str>,
+    tags: &[String],
+    options: &AddOptions,
+) -> Result<(), DipsError> {
+    let branch = options.branch.as_deref();
+    if options.global && branch.is_some() {
+        return Err(DipsError::validation(
+            "The global dips can't be attached to a branch",
+        ));
+    }
+    let tags = tag::split_names(tags);
+    if value_exists(app, value, options).await? {
+        return Err(DipsError::validation(format!(
+            "{value} is already added in this context"
+        )));
+    }
+
+    if options.global {
+        add_global(app, value, note, &tags).await?;
+    } else {
+        add_contextual(app, value, note, &tags, branch, options.here).await?;
+    }
 
-        println!("Dip {value} added.");
+    match branch {
+        Some(branch) => println!("Dip {value} added to the branch {branch}."),
+        None => println!("Dip {value} added."),
+    }
+    Ok(())
+}
+
+/// The branch to attach the dip to, the checked out one when no name is
+/// given.
+pub fn branch_name(app: &Application, name: Option<String>) -> Result<String, DipsError> {
+    if app.context_dir.git_dir_path().is_none() {
+        return Err(DipsError::validation(format!(
+            "{} is not in a git repository",
+            app.context_dir.path()
+        )));
+    }
+    match name.or_else(|| app.context_dir.branch().map(String::from)) {
+        Some(name) if !name.trim().is_empty() => Ok(name),
+        _ => Err(DipsError::validation(
+            "No branch is checked out, pass the name with --branch=<name>",
+        )),
     }
 }
@@ -0,0 +1,44 @@
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::git;
+use crate::models::dip;
+
+/// Archive the dips attached to the branches that are gone from the git
+/// repository of the current directory. Its linked worktrees share the
+/// branches, so their scopes are checked too, but not the ones of the other
+/// clones.
+pub async fn exec(app: &Application) -> Result<(), DipsError> {
+    let ctx = &app.context_dir;
+    let Some(git_root) = ctx.git_dir_path() else {
+        return Err(DipsError::validation(format!(
+            "{} is not in a git repository",
+            ctx.path()
+        )));
+    };
+    let mut branches = git::local_branches(git_root)?;
+    // A new branch has no commits yet, so it's not listed with the others.
+    branches.extend(ctx.branch().map(String::from));
+
+    let roots = git::worktree_roots(git_root)?;
+    let items = dip::get_branch_dips(&app.db_pool, &roots).await?;
+    let stale = items
+        .into_iter()
+        .filter(|x| !branches.contains(&x.branch))
+        .collect::<Vec<_>>();
+    if stale.is_empty() {
+        println!("No dips of deleted branches found.");
+        return Ok(());
+    }
+
+    let mut tx = app.db_pool.begin().await?;
+    for item in &stale {
+        dip::archive(&mut tx, &item.id).await?;
+    }
+    tx.commit().await?;
+
+    println!("Archived {} dips of deleted branches:", stale.len());
+    for item in &stale {
+        println!("  {} ({})", item.value, item.branch);
+    }
+    Ok(())
+}
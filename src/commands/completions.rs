@@ -0,0 +1,137 @@
+use crate::configuration::{Application, Environment, Settings};
+use crate::models::dip::{self, DipsFilter};
+use crate::models::dir_context::{self, RuntimeDirContext};
+use crate::models::tag;
+use clap_complete::env::Shells;
+use clap_complete::CompletionCandidate;
+use sqlx::SqlitePool;
+use std::future::Future;
+use std::path::Path;
+
+/// The environment variable the completion scripts call `dips` with.
+pub static COMPLETE_VAR: &str = "COMPLETE";
+
+/// The shells we can print the completions for.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Elvish,
+    Powershell,
+}
+
+impl CompletionShell {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::Elvish => "elvish",
+            Self::Powershell => "powershell",
+        }
+    }
+}
+
+/// Print the completion script for the shell. The script calls back into
+/// `dips` on every completion, so the selectors and tags come from the
+/// database, e.g. `source <(dips completions bash)`.
+pub fn exec(cmd: &clap::Command, shell: CompletionShell) {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(shell.name())
+        .expect("Every completion shell is a builtin one");
+    let name = cmd.get_name();
+    completer
+        .write_registration(COMPLETE_VAR, name, name, name, &mut std::io::stdout())
+        .expect("Failed to write to stdout");
+}
+
+/// Print the man page of `dips`, or write the pages of all the subcommands
+/// to the directory.
+pub fn man(cmd: clap::Command, out_dir: Option<&Path>) {
+    match out_dir {
+        Some(dir) => {
+            clap_mangen::generate_to(cmd, dir).expect("Failed to write the man pages");
+            println!("Man pages written to {}.", dir.display());
+        }
+        None => clap_mangen::Man::new(cmd)
+            .render(&mut std::io::stdout())
+            .expect("Failed to write to stdout"),
+    }
+}
+
+/// The candidates are queried while the shell waits, so we run them on the
+/// runtime that is already there, and skip the migrations.
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
+
+async fn connect() -> Option<Application> {
+    Application::connect(Settings::build(&Environment::current()))
+        .await
+        .ok()
+}
+
+/// The values of the dips in the current scope chain, with their notes as
+/// the description.
+pub fn selectors() -> Vec<CompletionCandidate> {
+    block_on(async {
+        match connect().await {
+            Some(app) => selector_candidates(&app.db_pool, &app.context_dir).await,
+            None => vec![],
+        }
+    })
+}
+
+pub async fn selector_candidates(
+    db_pool: &SqlitePool,
+    context_dir: &RuntimeDirContext,
+) -> Vec<CompletionCandidate> {
+    let Ok(chain) = dir_context::get_scope_chain(db_pool, context_dir, None).await else {
+        return vec![];
+    };
+    let filter = DipsFilter::new().with_branch(context_dir.branch());
+    dip::get_inherited(db_pool, &chain, filter)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|x| CompletionCandidate::new(x.value).help(x.note.map(Into::into)))
+        .collect()
+}
+
+/// The paths of all the scopes.
+pub fn scopes() -> Vec<CompletionCandidate> {
+    block_on(async {
+        let Some(app) = connect().await else {
+            return vec![];
+        };
+        dir_context::get_usage(&app.db_pool)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| CompletionCandidate::new(x.scope.dir_path))
+            .collect()
+    })
+}
+
+/// The names of all the existing tags.
+pub fn tags() -> Vec<CompletionCandidate> {
+    block_on(async {
+        match connect().await {
+            Some(app) => tag_candidates(&app.db_pool).await,
+            None => vec![],
+        }
+    })
+}
+
+pub async fn tag_candidates(db_pool: &SqlitePool) -> Vec<CompletionCandidate> {
+    let mut names = tag::get_usage(db_pool)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|x| x.name)
+        .collect::<Vec<_>>();
+    names.dedup();
+    names.into_iter().map(CompletionCandidate::new).collect()
+}
@@ -0,0 +1,139 @@
+use crate::configuration::{config_path, UserSettings};
+use crate::error::DipsError;
+use toml_edit::{DocumentMut, Item, Table};
+
+/// The keys that have no default, so they don't show up in the defaults.
+static OPTIONAL_KEYS: [&str; 3] = ["db_path", "depth", "editor"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetError {
+    #[error("Unknown config key {0}")]
+    UnknownKey(String),
+    #[error("The config file is not valid TOML: {0}")]
+    Parse(#[from] toml_edit::TomlError),
+    #[error("{0} in the config file is not a table")]
+    NotATable(String),
+    #[error("Invalid value for {key}: {source}")]
+    Invalid {
+        key: String,
+        source: toml::de::Error,
+    },
+}
+
+fn flatten(prefix: &str, value: &toml::Value, out: &mut Vec<String>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = match prefix {
+                    "" => key.to_owned(),
+                    _ => format!("{prefix}.{key}"),
+                };
+                flatten(&key, value, out);
+            }
+        }
+        _ => out.push(prefix.to_owned()),
+    }
+}
+
+/// All the keys that can be set, in the dotted form, e.g. `tui.keys.up`.
+pub fn keys() -> Vec<String> {
+    let defaults = toml::Value::try_from(UserSettings::default())
+        .expect("The default settings are valid TOML");
+    let mut keys = OPTIONAL_KEYS.map(String::from).to_vec();
+    flatten("", &defaults, &mut keys);
+    keys
+}
+
+/// Look up the dotted key in the settings, `None` when it's not set.
+pub fn lookup(settings: &UserSettings, key: &str) -> Option<toml::Value> {
+    let mut value = toml::Value::try_from(settings).ok()?;
+    for part in key.split('.') {
+        value = value.get(part)?.clone();
+    }
+    Some(value)
+}
+
+/// Set the key in the content of the config file. The rest of the file,
+/// the comments included, is kept as it was. Numbers are written as
+/// numbers when the setting takes them, everything else as a string.
+pub fn set_value(content: &str, key: &str, value: &str) -> Result<String, SetError> {
+    if !keys().iter().any(|x| x == key) {
+        return Err(SetError::UnknownKey(key.to_owned()));
+    }
+    let mut candidates = vec![];
+    if let Ok(number) = value.parse::<i64>() {
+        candidates.push(toml_edit::value(number));
+    }
+    candidates.push(toml_edit::value(value));
+
+    let mut last_error = None;
+    for candidate in candidates {
+        let mut doc = content.parse::<DocumentMut>()?;
+        let (parents, name) = match key.rsplit_once('.') {
+            Some((parents, name)) => (parents.split('.').collect(), name),
+            None => (vec![], key),
+        };
+        let mut table = doc.as_table_mut();
+        for part in parents {
+            let mut new_table = Table::new();
+            new_table.set_implicit(true);
+            table = table
+                .entry(part)
+                .or_insert(Item::Table(new_table))
+                .as_table_mut()
+                .ok_or_else(|| SetError::NotATable(part.to_owned()))?;
+        }
+        table[name] = candidate;
+
+        let content = doc.to_string();
+        match toml::from_str::<UserSettings>(&content) {
+            Ok(_) => return Ok(content),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(SetError::Invalid {
+        key: key.to_owned(),
+        source: last_error.expect("There is always a candidate"),
+    })
+}
+
+/// Print the effective settings, the defaults with the config file and the
+/// environment variables on top, or only the value of the key.
+pub fn get(key: Option<&str>) -> Result<(), DipsError> {
+    let settings = UserSettings::load()
+        .map_err(|e| DipsError::validation(format!("Failed to load the config: {e}")))?;
+    match key {
+        None => print!(
+            "{}",
+            toml::to_string(&settings).expect("The settings are valid TOML")
+        ),
+        Some(key) => match lookup(&settings, key) {
+            Some(toml::Value::String(value)) => println!("{value}"),
+            Some(value) => println!("{value}"),
+            None if keys().iter().any(|x| x == key) => println!("{key} is not set."),
+            None => return Err(SetError::UnknownKey(key.to_owned()).into()),
+        },
+    }
+    Ok(())
+}
+
+/// Write the key to the config file, creating the file when needed.
+pub fn set(key: &str, value: &str) -> Result<(), DipsError> {
+    let path = config_path();
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let content = set_value(&content, key, value)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, content)?;
+    println!("Set {key} to {value} in {}.", path.display());
+    Ok(())
+}
+
+pub fn path() {
+    println!("{}", config_path().display());
+}
@@ -1,20 +1,34 @@
-use crate::configuration;
+use crate::clipboard::{self, ClipboardBackend};
+use crate::commands::{edit, run, select};
+use crate::configuration::{self, KeyBindings, Settings};
+use crate::editor;
 use crate::models::dip::{self, DipRowFull, DipsFilter};
-use crate::models::dir_context::{self, DirContext, ScopesFilter};
-use crate::tui;
+use crate::models::dir_context::{
+    self, ContextScope, DirContext, ScopeLink, ScopeOrigin, ScopesFilter,
+};
+use crate::models::repo_file;
+use crate::models::tag_expr::TagExpr;
+use crate::models::template::{self, Placeholder};
+use crate::tui::{self, Theme};
 use color_eyre::eyre::WrapErr;
 use crossterm::event::{
     Event as CrosstermEvent, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
 };
 use futures_util::stream::StreamExt;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
-use ratatui::style::palette::tailwind::{GRAY, RED, SLATE};
 use ratatui::style::Style;
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, HighlightSpacing, List, ListItem, ListState, Paragraph};
+use ratatui::widgets::{
+    Block, Borders, HighlightSpacing, List, ListItem, ListState, Padding, Paragraph, Wrap,
+};
 use ratatui::Frame;
 use sqlx::SqlitePool;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
@@ -50,17 +64,32 @@ enum PromptState {
     },
     Search {
         input: String,
-        style: PromptStyle,
         state: SearchState,
     },
+    TagFilter {
+        input: String,
+    },
+    Edit {
+        id: Uuid,
+        input: String,
+    },
     Message {
-        value: &'static str,
+        value: Cow<'static, str>,
         style: PromptStyle,
     },
     Confirm {
         input: String,
         command: Command,
     },
+    /// Ask for the placeholders of the template one by one.
+    Template {
+        id: Uuid,
+        action: TemplateAction,
+        placeholders: Vec<Placeholder>,
+        last: HashMap<String, String>,
+        values: HashMap<String, String>,
+        input: String,
+    },
 }
 
 impl PromptState {
@@ -74,14 +103,21 @@ impl PromptState {
         *self = Self::Default
     }
 
-    fn activate_search_state(&mut self) {
+    fn activate_search_state(&mut self, input: String) {
         *self = Self::Search {
-            input: Default::default(),
-            style: Default::default(),
+            input,
             state: Default::default(),
         }
     }
 
+    fn activate_tag_filter_state(&mut self, input: String) {
+        *self = Self::TagFilter { input }
+    }
+
+    fn activate_edit_state(&mut self, id: Uuid, input: String) {
+        *self = Self::Edit { id, input }
+    }
+
     fn activate_nav_state(&mut self) {
         *self = Self::Nav;
     }
@@ -93,10 +129,34 @@ impl PromptState {
         }
     }
 
+    fn activate_template_state(
+        &mut self,
+        id: Uuid,
+        action: TemplateAction,
+        placeholders: Vec<Placeholder>,
+        last: HashMap<String, String>,
+    ) {
+        let input = placeholders
+            .first()
+            .and_then(|x| template::suggestion(x, &last))
+            .unwrap_or_default();
+        *self = Self::Template {
+            id,
+            action,
+            placeholders,
+            last,
+            values: HashMap::new(),
+            input,
+        }
+    }
+
     fn set_input(&mut self, c: char) -> bool {
         match self {
             Self::Search { input, .. }
             | Self::Input { input, .. }
+            | Self::TagFilter { input }
+            | Self::Edit { input, .. }
+            | Self::Template { input, .. }
             | Self::Confirm { input, .. } => {
                 input.push(c);
                 true
@@ -109,6 +169,9 @@ impl PromptState {
         match self {
             Self::Search { input, .. }
             | Self::Input { input, .. }
+            | Self::TagFilter { input }
+            | Self::Edit { input, .. }
+            | Self::Template { input, .. }
             | Self::Confirm { input, .. } => {
                 input.pop();
             }
@@ -139,6 +202,28 @@ impl PromptState {
                     self.set_error("Invalid command pattern");
                 }
             }
+            Self::TagFilter { input } => {
+                let expr = if input.trim().is_empty() {
+                    None
+                } else {
+                    match input.parse::<TagExpr>() {
+                        Ok(expr) => Some(expr),
+                        Err(_) => {
+                            self.set_error("Invalid tag filter");
+                            return;
+                        }
+                    }
+                };
+                let _ = dispatch.send(Event::Prompt(PromptEvent::Defocus));
+                let _ = dispatch.send(Event::Action(Action::FilterTags(expr)));
+            }
+            Self::Edit { id, input } => match edit::parse_value(input) {
+                Some(value) => {
+                    let command = Command::EditDip(*id, value.to_owned());
+                    let _ = dispatch.send(Event::Command(command));
+                }
+                None => self.set_error("The value can not be empty"),
+            },
             Self::Confirm { input, command } => match command {
                 Command::DeleteDip(id) => {
                     if input == "y" {
@@ -147,30 +232,80 @@ impl PromptState {
                         self.set_error("Only y is allowed");
                     }
                 }
+                Command::Run(id, value) => {
+                    if input == "y" {
+                        let command = Command::Run(*id, std::mem::take(value));
+                        let _ = dispatch.send(Event::Prompt(PromptEvent::Defocus));
+                        let _ = dispatch.send(Event::Command(command));
+                    } else {
+                        self.set_error("Only y is allowed");
+                    }
+                }
                 _ => todo!(),
             },
+            Self::Template {
+                id,
+                action,
+                placeholders,
+                last,
+                values,
+                input,
+            } => {
+                let name = placeholders[values.len()].name.to_owned();
+                values.insert(name, std::mem::take(input));
+                match placeholders.get(values.len()) {
+                    Some(next) => *input = template::suggestion(next, last).unwrap_or_default(),
+                    None => {
+                        let command = Command::TemplateFilled {
+                            id: *id,
+                            action: *action,
+                            values: std::mem::take(values),
+                        };
+                        let _ = dispatch.send(Event::Prompt(PromptEvent::Defocus));
+                        let _ = dispatch.send(Event::Command(command));
+                        self.activate_default_state();
+                    }
+                }
+            }
             _ => {
                 self.set_error("Invalid submit state");
             }
         }
     }
 
-    fn handle_message(&mut self, value: &'static str, style: PromptStyle) {
-        *self = Self::Message { value, style }
+    fn handle_message(&mut self, value: impl Into<Cow<'static, str>>, style: PromptStyle) {
+        *self = Self::Message {
+            value: value.into(),
+            style,
+        }
     }
 
-    fn get_search_input(&self) -> &str {
+    fn get_search_input(&self) -> Option<&str> {
         if let Self::Search { input, .. } = self {
-            input.as_str()
+            Some(input.as_str())
         } else {
-            ""
+            None
         }
     }
+
+    fn is_search_committed(&self) -> bool {
+        matches!(
+            self,
+            Self::Search {
+                state: SearchState::Commit,
+                ..
+            }
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 enum PageType {
-    Dips { scope_id: Option<Uuid> },
+    Dips {
+        scope_id: Option<Uuid>,
+        /// Show the dips of the whole inherited scope chain.
+        inherit: bool,
+    },
     Scopes,
     Help,
     Splash,
@@ -179,8 +314,11 @@ enum PageType {
 impl PageType {
     fn from_page(page: &PageState) -> Self {
         match page {
-            PageState::Dips { scope_id, .. } => Self::Dips {
-                scope_id: scope_id.clone(),
+            PageState::Dips {
+                scope_id, inherit, ..
+            } => Self::Dips {
+                scope_id: *scope_id,
+                inherit: *inherit,
             },
             PageState::Scopes { .. } => Self::Scopes,
             PageState::Help => Self::Help,
@@ -203,11 +341,17 @@ enum ScopesFocus {
     List,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 enum PageState {
+    #[default]
     Splash,
     Dips {
         scope_id: Option<Uuid>,
+        inherit: bool,
+        search: Option<String>,
+        tags: Option<TagExpr>,
+        /// Show the details of the selected dip next to the list.
+        details: bool,
         index: usize,
         items: Vec<Uuid>,
         focus: DipsFocus,
@@ -299,18 +443,92 @@ impl PageState {
         }
     }
 
+    fn get_search(&self) -> Option<&str> {
+        match self {
+            Self::Dips { search, .. } => search.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn action_search(&mut self, value: &str) {
+        if let Self::Dips { search, .. } = self {
+            let value = value.trim();
+            *search = if value.is_empty() {
+                None
+            } else {
+                Some(value.to_owned())
+            };
+        }
+    }
+
+    fn get_tags(&self) -> Option<&TagExpr> {
+        match self {
+            Self::Dips { tags, .. } => tags.as_ref(),
+            _ => None,
+        }
+    }
+
+    fn action_filter_tags(&mut self, expr: Option<TagExpr>) {
+        if let Self::Dips { tags, .. } = self {
+            *tags = expr;
+        }
+    }
+
+    fn action_toggle_details(&mut self) {
+        if let Self::Dips { details, .. } = self {
+            *details = !*details;
+        }
+    }
+
     fn fetch_data(&self, qm: &QueryManager) {
         match self {
-            PageState::Dips { scope_id, .. } => {
+            PageState::Dips {
+                scope_id,
+                inherit,
+                search,
+                tags,
+                ..
+            } => {
                 let pool = qm.db_pool.clone();
                 let sender = qm.sender.clone();
-                let filter = DipsFilter::new().with_scope_id(scope_id.clone());
+                let search = search.clone();
+                let chain = inherit.then(|| qm.scope_chain.clone());
+                let git_root = qm.git_root.clone();
+                let mut filter = DipsFilter::new()
+                    .with_scope_id(*scope_id)
+                    .with_tags(tags.clone());
+                if *inherit {
+                    filter = filter.with_branch(qm.branch.as_deref());
+                }
                 tokio::spawn(async move {
-                    let res = dip::get_filtered(&pool, filter)
-                        .await
-                        .expect("Failed to query filtered dips");
+                    let items = match chain {
+                        Some(chain) => {
+                            let mut items = dip::get_inherited(&pool, &chain, filter.clone())
+                                .await
+                                .expect("Failed to query filtered dips");
+                            let rows = repo_file::get_rows(git_root.as_deref())
+                                .unwrap_or_default()
+                                .into_iter()
+                                .filter(|x| match filter.tags() {
+                                    Some(expr) => x.tags.matches(expr),
+                                    None => true,
+                                })
+                                .collect();
+                            repo_file::merge_rows(&mut items, rows);
+                            items
+                        }
+                        None => dip::get_filtered(&pool, filter)
+                            .await
+                            .expect("Failed to query filtered dips"),
+                    };
+                    // The same matcher ranks every row and highlights the
+                    // matched characters.
+                    let items = match search.as_deref() {
+                        Some(search) => select::fuzzy_matches(items, search),
+                        None => items,
+                    };
                     if sender
-                        .send(Event::LoadData(DataPayload::Dips(res)))
+                        .send(Event::LoadData(DataPayload::Dips { search, items }))
                         .is_err()
                     {
                         todo!("report an error about the dispatch");
@@ -339,15 +557,15 @@ impl PageState {
     }
 }
 fn handle_add_command(state: &mut UiState, qm: &QueryManager, value: String) {
+    let page = state.page.page_type();
     match state.page {
         PageState::Dips { scope_id, .. } => {
             let pool = qm.db_pool.clone();
             let sender = qm.sender.clone();
             let _ = sender.send(Event::Prompt(PromptEvent::Defocus));
             tokio::spawn(async move {
-                let scope_id = scope_id.clone();
-                match dip::create(&pool, scope_id.clone(), &value, None).await {
-                    Ok(_) => sender.send(Event::RefetchData(PageType::Dips { scope_id })),
+                match dip::create(&pool, scope_id, &value, None).await {
+                    Ok(_) => sender.send(Event::RefetchData(page)),
                     Err(_) => sender.send(Event::Prompt(PromptEvent::Message {
                         msg: "Failed to add the dip",
                         style: PromptStyle::Danger,
@@ -366,10 +584,7 @@ fn handle_add_command(state: &mut UiState, qm: &QueryManager, value: String) {
 fn handle_delete_dip_command(state: &mut UiState, qm: &QueryManager, id: Uuid) {
     let pool = qm.db_pool.clone();
     let sender = qm.sender.clone();
-    let scope_id = match state.page {
-        PageState::Dips { scope_id, .. } => scope_id.clone(),
-        _ => None,
-    };
+    let page = state.page.page_type();
     let _ = sender.send(Event::Prompt(PromptEvent::Defocus));
     tokio::spawn(async move {
         match dip::delete(&pool, &id).await {
@@ -378,7 +593,7 @@ fn handle_delete_dip_command(state: &mut UiState, qm: &QueryManager, id: Uuid) {
                     msg: "Dip deleted",
                     style: PromptStyle::Info,
                 }));
-                let _ = sender.send(Event::RefetchData(PageType::Dips { scope_id }));
+                let _ = sender.send(Event::RefetchData(page));
             }
             Err(_) => {
                 let _ = sender.send(Event::Prompt(PromptEvent::Message {
@@ -390,10 +605,87 @@ fn handle_delete_dip_command(state: &mut UiState, qm: &QueryManager, id: Uuid) {
     });
 }
 
-impl Default for PageState {
-    fn default() -> Self {
-        Self::Splash
-    }
+fn handle_edit_dip_command(state: &mut UiState, qm: &QueryManager, id: Uuid, value: String) {
+    let pool = qm.db_pool.clone();
+    let sender = qm.sender.clone();
+    let page = state.page.page_type();
+    let _ = sender.send(Event::Prompt(PromptEvent::Defocus));
+    tokio::spawn(async move {
+        match dip::update(&pool, &id, Some(&value), None).await {
+            Ok(_) => {
+                let _ = sender.send(Event::Prompt(PromptEvent::Message {
+                    msg: "Dip updated",
+                    style: PromptStyle::Info,
+                }));
+                let _ = sender.send(Event::RefetchData(page));
+            }
+            Err(_) => {
+                let _ = sender.send(Event::Prompt(PromptEvent::Message {
+                    msg: "Failed to update the dip",
+                    style: PromptStyle::Danger,
+                }));
+            }
+        }
+    });
+}
+
+fn handle_set_note_command(state: &UiState, qm: &QueryManager, id: Uuid, note: Option<String>) {
+    let pool = qm.db_pool.clone();
+    let sender = qm.sender.clone();
+    let page = state.page.page_type();
+    tokio::spawn(async move {
+        match dip::update(&pool, &id, None, Some(note.as_deref())).await {
+            Ok(_) => {
+                let _ = sender.send(Event::Prompt(PromptEvent::Message {
+                    msg: "Note updated",
+                    style: PromptStyle::Info,
+                }));
+                let _ = sender.send(Event::RefetchData(page));
+            }
+            Err(_) => {
+                let _ = sender.send(Event::Prompt(PromptEvent::Message {
+                    msg: "Failed to update the note",
+                    style: PromptStyle::Danger,
+                }));
+            }
+        }
+    });
+}
+
+fn handle_fill_template_command(
+    qm: &QueryManager,
+    id: Uuid,
+    action: TemplateAction,
+    scope: Option<String>,
+) {
+    let pool = qm.db_pool.clone();
+    let sender = qm.sender.clone();
+    tokio::spawn(async move {
+        match template::get_last_values(&pool, scope.as_deref()).await {
+            Ok(last) => {
+                let _ = sender.send(Event::Prompt(PromptEvent::Template { id, action, last }));
+            }
+            Err(_) => {
+                let _ = sender.send(Event::Prompt(PromptEvent::Message {
+                    msg: "Failed to read the placeholder values",
+                    style: PromptStyle::Danger,
+                }));
+            }
+        }
+    });
+}
+
+/// Remember the placeholder values for the next time. Nothing waits for it,
+/// so the failure is not worth interrupting the user.
+fn handle_save_template_command(
+    qm: &QueryManager,
+    scope: Option<String>,
+    values: HashMap<String, String>,
+) {
+    let pool = qm.db_pool.clone();
+    tokio::spawn(async move {
+        let _ = template::save_values(&pool, scope.as_deref(), &values).await;
+    });
 }
 
 #[derive(Debug)]
@@ -424,8 +716,12 @@ impl Default for UiState {
 impl UiState {
     fn from_type(page: &PageType) -> PageState {
         match page {
-            PageType::Dips { scope_id } => PageState::Dips {
+            PageType::Dips { scope_id, inherit } => PageState::Dips {
                 scope_id: *scope_id,
+                inherit: *inherit,
+                search: None,
+                tags: None,
+                details: false,
                 index: 0,
                 items: vec![],
                 focus: DipsFocus::default(),
@@ -459,34 +755,33 @@ impl UiState {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct DataState {
     dips: HashMap<Uuid, DipRowFull>,
     scopes: HashMap<Uuid, DirContext>,
 }
 
-impl Default for DataState {
-    fn default() -> Self {
-        Self {
-            dips: HashMap::new(),
-            scopes: HashMap::new(),
-        }
-    }
-}
-
 #[derive(Debug)]
 struct AppState {
     mode: Mode,
     ui: UiState,
     data: DataState,
+    theme: Theme,
+    keys: KeyBindings,
+    editor: String,
+    clipboard: ClipboardBackend,
 }
 
 impl AppState {
-    pub fn new() -> Self {
+    pub fn new(settings: &Settings) -> Self {
         Self {
             mode: Mode::default(),
             ui: UiState::default(),
             data: DataState::default(),
+            theme: settings.user.tui.theme.into(),
+            keys: settings.user.tui.keys.clone(),
+            editor: settings.editor(),
+            clipboard: settings.user.clipboard,
         }
     }
 
@@ -494,7 +789,30 @@ impl AppState {
         self.mode == Mode::Running
     }
 
+    /// The dips from the repository file can only be changed in the file.
+    fn is_read_only(&self, id: &Uuid) -> bool {
+        self.data
+            .dips
+            .get(id)
+            .is_some_and(|x| x.origin == Some(ScopeOrigin::RepoFile))
+    }
+
+    /// The dip under the cursor when the list of the dips page has the focus.
+    fn selected_dip(&self) -> Option<Uuid> {
+        match &self.ui.page {
+            PageState::Dips {
+                focus: DipsFocus::List,
+                index,
+                items,
+                ..
+            } => items.get(*index).copied(),
+            _ => None,
+        }
+    }
+
     fn load_dips_page(&mut self, items: Vec<DipRowFull>) {
+        // Keep the order of the query as the search results are ranked.
+        let ids = items.iter().map(|x| x.id).collect::<Vec<_>>();
         self.data.dips = items.into_iter().map(|x| (x.id.to_owned(), x)).collect();
         match self.ui.page {
             PageState::Dips {
@@ -502,8 +820,12 @@ impl AppState {
                 ref mut index,
                 ..
             } => {
-                *items = self.data.dips.iter().map(|(id, _)| id.clone()).collect();
-                *index = 0;
+                // Stay on the same dip after it's changed and the page is refetched.
+                let selected = items.get(*index);
+                *index = selected
+                    .and_then(|id| ids.iter().position(|x| x == id))
+                    .unwrap_or(0);
+                *items = ids;
             }
             _ => unreachable!(),
         };
@@ -511,28 +833,117 @@ impl AppState {
 
     fn load_scopes_page(&mut self, items: Vec<DirContext>) {
         self.data.scopes = items.into_iter().map(|x| (x.id.to_owned(), x)).collect();
-        match self.ui.page {
-            PageState::Scopes {
-                ref mut items,
-                ref mut index,
-                ..
-            } => {
-                *items = self.data.scopes.iter().map(|(id, _)| id.clone()).collect();
-                *index = 0;
-            }
-            _ => {}
+        if let PageState::Scopes {
+            ref mut items,
+            ref mut index,
+            ..
+        } = self.ui.page
+        {
+            *items = self.data.scopes.keys().copied().collect();
+            *index = 0;
         };
     }
 }
 
+/// Split the value into spans where the characters matching the search
+/// are highlighted.
+fn highlight_matches<'a>(value: &'a str, search: Option<&str>, theme: &Theme) -> Vec<Span<'a>> {
+    let indices = search
+        .and_then(|search| SkimMatcherV2::default().fuzzy_indices(value, search))
+        .map(|(_, indices)| indices)
+        .unwrap_or_default();
+    if indices.is_empty() {
+        return vec![Span::raw(value)];
+    }
+
+    let mut spans = vec![];
+    let mut chunk = String::new();
+    let mut chunk_matched = false;
+    for (i, c) in value.chars().enumerate() {
+        let matched = indices.contains(&i);
+        if matched != chunk_matched && !chunk.is_empty() {
+            spans.push(highlight_span(
+                std::mem::take(&mut chunk),
+                chunk_matched,
+                theme,
+            ));
+        }
+        chunk_matched = matched;
+        chunk.push(c);
+    }
+    spans.push(highlight_span(chunk, chunk_matched, theme));
+    spans
+}
+
+fn highlight_span<'a>(value: String, matched: bool, theme: &Theme) -> Span<'a> {
+    if matched {
+        Span::styled(value, Style::new().fg(theme.accent))
+    } else {
+        Span::raw(value)
+    }
+}
+
+/// What the dips page shows next to the list: the search and the tag filter
+/// it is narrowed down with and whether the details pane is open.
+struct DipsPageView<'a> {
+    search: Option<&'a str>,
+    tags: Option<&'a TagExpr>,
+    details: bool,
+    theme: &'a Theme,
+}
+
+fn render_dip_details(item: &DipRowFull, theme: &Theme, area: Rect, frame: &mut Frame) {
+    let label = Style::new().fg(theme.muted);
+    let mut lines = vec![Line::from(item.value.as_str()), Line::raw("")];
+    match item.note.as_deref() {
+        Some(note) => lines.extend(note.lines().map(Line::raw)),
+        None => lines.push(Line::styled("No note", label)),
+    }
+    lines.push(Line::raw(""));
+
+    let scope = match (item.dir_path.as_deref(), item.origin) {
+        (Some(path), Some(origin)) => format!("{path} ({})", origin.label()),
+        (Some(path), None) => path.to_owned(),
+        (None, _) => "Global".to_owned(),
+    };
+    let fields = [
+        ("Tags", item.tags.to_string()),
+        ("Scope", scope),
+        (
+            "Created",
+            item.created_at.format("%Y-%m-%d %H:%M").to_string(),
+        ),
+        (
+            "Updated",
+            item.updated_at.format("%Y-%m-%d %H:%M").to_string(),
+        ),
+    ];
+    for (name, value) in fields {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{name}: "), label),
+            Span::raw(value),
+        ]));
+    }
+
+    let pane = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::new()
+            .borders(Borders::LEFT)
+            .border_style(label)
+            .padding(Padding::horizontal(1)),
+    );
+    frame.render_widget(pane, area);
+}
+
 fn render_dips_page(
     scope: Option<&DirContext>,
+    view: DipsPageView,
     items: Vec<&DipRowFull>,
     index: usize,
     focus: &DipsFocus,
     area: Rect,
     frame: &mut Frame,
 ) {
+    let theme = view.theme;
     let page_layout = Layout::new(
         Direction::Vertical,
         [
@@ -550,13 +961,21 @@ fn render_dips_page(
     if let Some(scope) = scope {
         scope_text.push(Span::raw(" "));
         scope_text.push(Span::styled(
-            scope.git_remote.as_ref().map(|x| x.as_str()).unwrap_or(""),
-            Style::new().fg(SLATE.c500),
+            scope.git_remote.as_deref().unwrap_or(""),
+            Style::new().fg(theme.border),
+        ));
+    }
+
+    if let Some(tags) = view.tags {
+        scope_text.push(Span::raw(" "));
+        scope_text.push(Span::styled(
+            format!("[tags: {tags}]"),
+            Style::new().fg(theme.accent),
         ));
     }
 
     let scope_style = match focus {
-        DipsFocus::Scope => Style::new().bg(SLATE.c800),
+        DipsFocus::Scope => Style::new().bg(theme.bar),
         DipsFocus::List => Style::new(),
     };
 
@@ -565,29 +984,60 @@ fn render_dips_page(
         header,
     );
     frame.render_widget(
-        Paragraph::new(Span::styled("-------", Style::new().fg(GRAY.c500))),
+        Paragraph::new(Span::styled("-------", Style::new().fg(theme.muted))),
         border,
     );
 
-    let index = if items.len() > 0 && matches!(focus, DipsFocus::List) {
+    let index = if !items.is_empty() && matches!(focus, DipsFocus::List) {
         Some(index)
     } else {
         None
     };
+    let selected = index.and_then(|i| items.get(i)).copied();
+    let main = match selected {
+        Some(item) if view.details => {
+            let layout = Layout::new(
+                Direction::Horizontal,
+                [Constraint::Percentage(55), Constraint::Percentage(45)],
+            );
+            let [list, pane] = layout.areas(main);
+            render_dip_details(item, theme, pane, frame);
+            list
+        }
+        _ => main,
+    };
 
     let items = items
         .iter()
-        .map(|x| {
-            ListItem::new(Line::from(vec![
-                Span::raw(x.value.as_str()),
-                Span::raw(" "),
-                Span::from(format!("{}", x.tags.to_string())).style(Style::new().fg(SLATE.c500)),
-            ]))
+        .enumerate()
+        .map(|(i, x)| {
+            let mut line = highlight_matches(x.value.as_str(), view.search, theme);
+            line.push(Span::raw(" "));
+            line.push(Span::from(x.tags.to_string()).style(Style::new().fg(theme.border)));
+
+            // The inherited dips come grouped by their origin, so we mark where
+            // every group, other than the closest scope, starts.
+            let group_start =
+                i > 0 && (items[i - 1].origin, &items[i - 1].dir_path) != (x.origin, &x.dir_path);
+            match x.origin {
+                Some(origin) if group_start => {
+                    let header = format!(
+                        "{} ({})",
+                        x.dir_path.as_deref().unwrap_or("Global"),
+                        origin.label()
+                    );
+                    ListItem::new(vec![
+                        Line::styled(header, Style::new().fg(theme.muted)),
+                        Line::from(line),
+                    ])
+                }
+                _ => ListItem::new(Line::from(line)),
+            }
         })
         .collect::<Vec<_>>();
     let list = List::new(items)
         .block(Block::new())
-        .highlight_style(Style::new().bg(SLATE.c800))
+        .highlight_style(Style::new().bg(theme.bar))
         .highlight_symbol("> ")
         .highlight_spacing(HighlightSpacing::Never);
 
@@ -595,22 +1045,34 @@ fn render_dips_page(
     frame.render_stateful_widget(list, main, &mut state);
 }
 
-fn render_prompt(prompt: &PromptState, area: Rect, frame: &mut Frame) {
+fn render_prompt(
+    prompt: &PromptState,
+    theme: &Theme,
+    keys: &KeyBindings,
+    area: Rect,
+    frame: &mut Frame,
+) {
     match prompt {
         PromptState::Default => {
             let layout = Layout::new(Direction::Horizontal, Constraint::from_fills([1, 1]));
             let [left, right] = layout.areas(area);
-            let left_widget =
-                Line::from("Type : to start a command").style(Style::new().fg(GRAY.c500));
+            let left_widget = Line::from(format!("Type {} to start a command", keys.command))
+                .style(Style::new().fg(theme.muted));
             let right_widget = Line::from(vec![
                 Span::raw("   Search "),
-                Span::styled(" / ", Style::new().bg(SLATE.c800).fg(GRAY.c400)),
+                Span::styled(
+                    format!(" {} ", keys.search),
+                    Style::new().bg(theme.bar).fg(theme.key),
+                ),
                 Span::raw("   Help "),
-                Span::styled(" ? ", Style::new().bg(SLATE.c800).fg(GRAY.c400)),
+                Span::styled(
+                    format!(" {} ", keys.help),
+                    Style::new().bg(theme.bar).fg(theme.key),
+                ),
                 Span::raw("   Exit "),
-                Span::styled(" C-c ", Style::new().bg(SLATE.c800).fg(GRAY.c400)),
+                Span::styled(" C-c ", Style::new().bg(theme.bar).fg(theme.key)),
             ])
-            .style(Style::new().fg(GRAY.c200))
+            .style(Style::new().fg(theme.text))
             .alignment(Alignment::Right);
 
             frame.render_widget(left_widget, left);
@@ -619,9 +1081,9 @@ fn render_prompt(prompt: &PromptState, area: Rect, frame: &mut Frame) {
         PromptState::Nav => {
             let line = Line::from(vec![
                 Span::raw(" Go back "),
-                Span::styled(" Esc ", Style::new().bg(SLATE.c800).fg(GRAY.c400)),
+                Span::styled(" Esc ", Style::new().bg(theme.bar).fg(theme.key)),
             ])
-            .style(Style::new().fg(GRAY.c200))
+            .style(Style::new().fg(theme.text))
             .alignment(Alignment::Left);
             frame.render_widget(line, area);
         }
@@ -632,51 +1094,122 @@ fn render_prompt(prompt: &PromptState, area: Rect, frame: &mut Frame) {
             );
             let [left, right] = layout.areas(area);
             let left_widget = Line::from(vec![Span::raw("Command: "), Span::from(input)])
-                .style(Style::new().bg(SLATE.c800));
+                .style(Style::new().bg(theme.bar));
+            let right_widget = Line::from(vec![
+                Span::styled("To cancel ", Style::new().fg(theme.muted)),
+                Span::styled(" Esc ", Style::new().bg(theme.badge).fg(theme.key)),
+            ])
+            .style(Style::new().fg(theme.faint).bg(theme.bar))
+            .alignment(Alignment::Right);
+            frame.render_widget(left_widget, left);
+            frame.render_widget(right_widget, right);
+        }
+        PromptState::Search { input, state } => {
+            let layout = Layout::new(
+                Direction::Horizontal,
+                [Constraint::Min(0), Constraint::Length(20)],
+            );
+            let [left, right] = layout.areas(area);
+            let (input_style, hint) = match state {
+                SearchState::Active => (Style::new(), "To cancel "),
+                SearchState::Commit => (Style::new().fg(theme.key), "To clear "),
+            };
+            let left_widget = Line::from(vec![
+                Span::raw("Search: "),
+                Span::styled(input, input_style),
+            ])
+            .style(Style::new().bg(theme.bar));
+            let right_widget = Line::from(vec![
+                Span::styled(hint, Style::new().fg(theme.muted)),
+                Span::styled(" Esc ", Style::new().bg(theme.badge).fg(theme.key)),
+            ])
+            .style(Style::new().fg(theme.faint).bg(theme.bar))
+            .alignment(Alignment::Right);
+            frame.render_widget(left_widget, left);
+            frame.render_widget(right_widget, right);
+        }
+        PromptState::TagFilter { input } => {
+            let layout = Layout::new(
+                Direction::Horizontal,
+                [Constraint::Min(0), Constraint::Length(20)],
+            );
+            let [left, right] = layout.areas(area);
+            let left_widget = Line::from(vec![Span::raw("Tags: "), Span::from(input)])
+                .style(Style::new().bg(theme.bar));
+            let right_widget = Line::from(vec![
+                Span::styled("To cancel ", Style::new().fg(theme.muted)),
+                Span::styled(" Esc ", Style::new().bg(theme.badge).fg(theme.key)),
+            ])
+            .style(Style::new().fg(theme.faint).bg(theme.bar))
+            .alignment(Alignment::Right);
+            frame.render_widget(left_widget, left);
+            frame.render_widget(right_widget, right);
+        }
+        PromptState::Edit { input, .. } => {
+            let layout = Layout::new(
+                Direction::Horizontal,
+                [Constraint::Min(0), Constraint::Length(20)],
+            );
+            let [left, right] = layout.areas(area);
+            let left_widget = Line::from(vec![Span::raw("Edit: "), Span::from(input)])
+                .style(Style::new().bg(theme.bar));
             let right_widget = Line::from(vec![
-                Span::styled("To cancel ", Style::new().fg(GRAY.c500)),
-                Span::styled(" Esc ", Style::new().bg(SLATE.c600).fg(GRAY.c400)),
+                Span::styled("To cancel ", Style::new().fg(theme.muted)),
+                Span::styled(" Esc ", Style::new().bg(theme.badge).fg(theme.key)),
             ])
-            .style(Style::new().fg(GRAY.c600).bg(SLATE.c800))
+            .style(Style::new().fg(theme.faint).bg(theme.bar))
             .alignment(Alignment::Right);
             frame.render_widget(left_widget, left);
             frame.render_widget(right_widget, right);
         }
-        PromptState::Search { input, .. } => {
+        PromptState::Template {
+            placeholders,
+            values,
+            input,
+            ..
+        } => {
             let layout = Layout::new(
                 Direction::Horizontal,
                 [Constraint::Min(0), Constraint::Length(20)],
             );
             let [left, right] = layout.areas(area);
-            let left_widget = Line::from(vec![Span::raw("Search: "), Span::from(input)])
-                .style(Style::new().bg(SLATE.c800));
+            let name = &placeholders[values.len()].name;
+            let left_widget = Line::from(vec![
+                Span::styled(format!("{name}: "), Style::new().fg(theme.accent)),
+                Span::from(input),
+            ])
+            .style(Style::new().bg(theme.bar));
             let right_widget = Line::from(vec![
-                Span::styled("To cancel ", Style::new().fg(GRAY.c500)),
-                Span::styled(" Esc ", Style::new().bg(SLATE.c600).fg(GRAY.c400)),
+                Span::styled("To cancel ", Style::new().fg(theme.muted)),
+                Span::styled(" Esc ", Style::new().bg(theme.badge).fg(theme.key)),
             ])
-            .style(Style::new().fg(GRAY.c600).bg(SLATE.c800))
+            .style(Style::new().fg(theme.faint).bg(theme.bar))
             .alignment(Alignment::Right);
             frame.render_widget(left_widget, left);
             frame.render_widget(right_widget, right);
         }
         PromptState::Confirm { input, command } => match command {
-            Command::DeleteDip(_) => {
+            Command::DeleteDip(_) | Command::Run(..) => {
                 let layout = Layout::new(
                     Direction::Horizontal,
                     [Constraint::Min(0), Constraint::Length(20)],
                 );
                 let [left, right] = layout.areas(area);
+                let action = match command {
+                    Command::Run(..) => "RUN: ",
+                    _ => "DELETE: ",
+                };
                 let left_widget = Line::from(vec![
-                    Span::raw("DELETE: "),
+                    Span::raw(action),
                     Span::raw("Are you sure? (y) "),
                     Span::from(input),
                 ])
-                .style(Style::new().bg(SLATE.c800));
+                .style(Style::new().bg(theme.bar));
                 let right_widget = Line::from(vec![
-                    Span::styled("To cancel ", Style::new().fg(GRAY.c500)),
-                    Span::styled(" Esc ", Style::new().bg(SLATE.c600).fg(GRAY.c400)),
+                    Span::styled("To cancel ", Style::new().fg(theme.muted)),
+                    Span::styled(" Esc ", Style::new().bg(theme.badge).fg(theme.key)),
                 ])
-                .style(Style::new().fg(GRAY.c600).bg(SLATE.c800))
+                .style(Style::new().fg(theme.faint).bg(theme.bar))
                 .alignment(Alignment::Right);
                 frame.render_widget(left_widget, left);
                 frame.render_widget(right_widget, right);
@@ -685,27 +1218,33 @@ fn render_prompt(prompt: &PromptState, area: Rect, frame: &mut Frame) {
         },
         PromptState::Message { value, style } => {
             let type_style = match style {
-                PromptStyle::Danger => Style::new().fg(RED.c500),
+                PromptStyle::Danger => Style::new().fg(theme.danger),
                 PromptStyle::Info => Style::default(),
-                _ => todo!()
+                _ => todo!(),
             };
             let tag = match style {
                 PromptStyle::Danger => "Error",
                 PromptStyle::Info => "Info",
-                _ => todo!()
+                _ => todo!(),
             };
             let layout = Layout::new(Direction::Horizontal, Constraint::from_fills([1, 1]));
             let [left, right] = layout.areas(area);
             let left_widget = Line::from(format!("{}: {}", tag, value)).style(type_style);
             let right_widget = Line::from(vec![
                 Span::raw("   Search "),
-                Span::styled(" / ", Style::new().bg(SLATE.c800).fg(GRAY.c400)),
+                Span::styled(
+                    format!(" {} ", keys.search),
+                    Style::new().bg(theme.bar).fg(theme.key),
+                ),
                 Span::raw("   Help "),
-                Span::styled(" ? ", Style::new().bg(SLATE.c800).fg(GRAY.c400)),
+                Span::styled(
+                    format!(" {} ", keys.help),
+                    Style::new().bg(theme.bar).fg(theme.key),
+                ),
                 Span::raw("   Exit "),
-                Span::styled(" C-c ", Style::new().bg(SLATE.c800).fg(GRAY.c400)),
+                Span::styled(" C-c ", Style::new().bg(theme.bar).fg(theme.key)),
             ])
-            .style(Style::new().fg(GRAY.c200))
+            .style(Style::new().fg(theme.text))
             .alignment(Alignment::Right);
 
             frame.render_widget(left_widget, left);
@@ -725,6 +1264,7 @@ fn render_scopes_page(
     items: Vec<&DirContext>,
     index: usize,
     focus: &ScopesFocus,
+    theme: &Theme,
     area: Rect,
     frame: &mut Frame,
 ) {
@@ -739,10 +1279,10 @@ fn render_scopes_page(
     let [header, border, main] = page_layout.areas(area);
     frame.render_widget(Paragraph::new(Line::from("Your scopes:")), header);
     frame.render_widget(
-        Paragraph::new(Span::styled("-------", Style::new().fg(GRAY.c500))),
+        Paragraph::new(Span::styled("-------", Style::new().fg(theme.muted))),
         border,
     );
-    let index = if items.len() > 0 && matches!(focus, ScopesFocus::List) {
+    let index = if !items.is_empty() && matches!(focus, ScopesFocus::List) {
         Some(index)
     } else {
         None
@@ -756,7 +1296,7 @@ fn render_scopes_page(
     let global_item = Paragraph::new(Line::from("Global"));
     let global_item_styles = match focus {
         ScopesFocus::List => Style::new(),
-        ScopesFocus::Global => Style::new().bg(SLATE.c800),
+        ScopesFocus::Global => Style::new().bg(theme.bar),
     };
 
     frame.render_widget(global_item.style(global_item_styles), global_line);
@@ -764,17 +1304,17 @@ fn render_scopes_page(
     let items = items
         .iter()
         .map(|x| {
-            let git_remote = x.git_remote.as_ref().map(|x| x.as_str()).unwrap_or("");
+            let git_remote = x.git_remote.as_deref().unwrap_or("");
             ListItem::new(Line::from(vec![
                 Span::raw(x.dir_path.as_str()),
                 Span::raw(" "),
-                Span::from(git_remote).style(Style::new().fg(SLATE.c500)),
+                Span::from(git_remote).style(Style::new().fg(theme.border)),
             ]))
         })
         .collect::<Vec<_>>();
     let list = List::new(items)
         .block(Block::new())
-        .highlight_style(Style::new().bg(SLATE.c800))
+        .highlight_style(Style::new().bg(theme.bar))
         .highlight_symbol("> ")
         .highlight_spacing(HighlightSpacing::Never);
 
@@ -794,13 +1334,23 @@ fn render_page_with_prompt(state: &AppState, frame: &mut Frame) {
             index,
             focus,
             scope_id,
+            search,
+            tags,
+            details,
+            ..
         } => {
             let items = items
                 .iter()
                 .filter_map(|id| state.data.dips.get(id))
                 .collect::<Vec<_>>();
             let scope = scope_id.and_then(|id| state.data.scopes.get(&id));
-            render_dips_page(scope, items, *index, focus, page, frame);
+            let view = DipsPageView {
+                search: search.as_deref(),
+                tags: tags.as_ref(),
+                details: *details,
+                theme: &state.theme,
+            };
+            render_dips_page(scope, view, items, *index, focus, page, frame);
         }
         PageState::Help => {
             render_help_page(page, frame);
@@ -815,21 +1365,27 @@ fn render_page_with_prompt(state: &AppState, frame: &mut Frame) {
                 .iter()
                 .filter_map(|id| state.data.scopes.get(id))
                 .collect::<Vec<_>>();
-            render_scopes_page(items, *index, focus, page, frame);
+            render_scopes_page(items, *index, focus, &state.theme, page, frame);
         }
     };
-    render_prompt(&state.ui.prompt, prompt, frame);
+    render_prompt(&state.ui.prompt, &state.theme, &state.keys, prompt, frame);
 }
 
 #[derive(Debug)]
 enum DataPayload {
-    Dips(Vec<DipRowFull>),
+    /// The search the dips were queried with, so we can drop the results
+    /// of the stale queries while the user is still typing.
+    Dips {
+        search: Option<String>,
+        items: Vec<DipRowFull>,
+    },
     Scopes(Vec<DirContext>),
 }
 
 #[derive(Debug)]
 enum SearchMode {
     Init,
+    Clear,
 }
 
 #[derive(Debug)]
@@ -837,6 +1393,17 @@ enum PromptEvent {
     Focus,
     Defocus,
     Search(SearchMode),
+    /// Open the prompt for the tag filter expression.
+    TagFilter,
+    /// Open the prompt with the value of the dip to change it.
+    Edit(Uuid),
+    /// Open the prompt for the placeholders of the dip, with the values
+    /// last used in its scope.
+    Template {
+        id: Uuid,
+        action: TemplateAction,
+        last: HashMap<String, String>,
+    },
     Confirm(Command),
     Input(char),
     InputBackspace,
@@ -851,12 +1418,45 @@ enum PromptEvent {
 enum Action {
     MoveUp,
     MoveDown,
+    Search(String),
+    FilterTags(Option<TagExpr>),
+    ToggleDetails,
 }
 
 #[derive(Debug)]
 enum Command {
     Add(String),
     DeleteDip(Uuid),
+    EditDip(Uuid, String),
+    /// Edit the note in the external editor.
+    EditNote(Uuid),
+    /// Copy the value to the clipboard.
+    Copy(String),
+    /// Run the value in the shell, in the directory of the dip's scope.
+    Run(Uuid, String),
+    /// Ask for the placeholders of the dip before running or copying it.
+    FillTemplate(Uuid, TemplateAction),
+    TemplateFilled {
+        id: Uuid,
+        action: TemplateAction,
+        values: HashMap<String, String>,
+    },
+}
+
+/// What to do with a template once its placeholders are filled.
+#[derive(Debug, Clone, Copy)]
+enum TemplateAction {
+    Run,
+    Copy,
+}
+
+/// Running the commands that look destructive has to be confirmed first.
+fn run_or_confirm(id: Uuid, value: String) -> Event {
+    if run::is_dangerous(&value) {
+        Event::Prompt(PromptEvent::Confirm(Command::Run(id, value)))
+    } else {
+        Event::Command(Command::Run(id, value))
+    }
 }
 
 #[derive(Debug)]
@@ -872,8 +1472,18 @@ enum Event {
     QuitSignal,
 }
 
+/// Wait for the next terminal event, or forever while the events are paused.
+async fn next_terminal_event(
+    stream: &mut Option<EventStream>,
+) -> Option<std::io::Result<CrosstermEvent>> {
+    match stream {
+        Some(stream) => stream.next().await,
+        None => std::future::pending().await,
+    }
+}
+
 struct EventService {
-    crossterm_events: EventStream,
+    crossterm_events: Option<EventStream>,
     events: mpsc::UnboundedReceiver<Event>,
     dispatcher: mpsc::UnboundedSender<Event>,
 }
@@ -884,7 +1494,7 @@ impl EventService {
         dispatcher: mpsc::UnboundedSender<Event>,
     ) -> Self {
         Self {
-            crossterm_events: EventStream::new(),
+            crossterm_events: Some(EventStream::new()),
             events,
             dispatcher,
         }
@@ -905,13 +1515,50 @@ impl EventService {
     }
 
     fn handle_page_events(event: &KeyEvent, ctx: &AppState) -> Option<Event> {
+        let keys = &ctx.keys;
         match event.code {
-            KeyCode::Char('?') => Some(Event::Nav(PageType::Help)),
-            KeyCode::Char('j') | KeyCode::Down => Some(Event::Action(Action::MoveDown)),
-            KeyCode::Char('k') | KeyCode::Up => Some(Event::Action(Action::MoveUp)),
-            KeyCode::Char(':') => Some(Event::Prompt(PromptEvent::Focus)),
-            KeyCode::Char('/') => Some(Event::Prompt(PromptEvent::Search(SearchMode::Init))),
-            KeyCode::Char('d') => match &ctx.ui.page {
+            KeyCode::Char(c) if c == keys.help => Some(Event::Nav(PageType::Help)),
+            KeyCode::Down => Some(Event::Action(Action::MoveDown)),
+            KeyCode::Char(c) if c == keys.down => Some(Event::Action(Action::MoveDown)),
+            KeyCode::Up => Some(Event::Action(Action::MoveUp)),
+            KeyCode::Char(c) if c == keys.up => Some(Event::Action(Action::MoveUp)),
+            KeyCode::Char(c) if c == keys.command => Some(Event::Prompt(PromptEvent::Focus)),
+            KeyCode::Char(c) if c == keys.search => {
+                Some(Event::Prompt(PromptEvent::Search(SearchMode::Init)))
+            }
+            KeyCode::Char(c) if c == keys.edit || c == keys.note => match ctx.selected_dip() {
+                Some(id) if ctx.is_read_only(&id) => Some(Event::Prompt(PromptEvent::Message {
+                    msg: "Dips from the repository file are read-only",
+                    style: PromptStyle::Danger,
+                })),
+                Some(id) if c == keys.edit => Some(Event::Prompt(PromptEvent::Edit(id))),
+                Some(id) => Some(Event::Command(Command::EditNote(id))),
+                None => None,
+            },
+            KeyCode::Char(c) if c == keys.run || c == keys.copy => {
+                ctx.selected_dip().and_then(|id| {
+                    let value = ctx.data.dips.get(&id)?.value.to_owned();
+                    let action = if c == keys.run {
+                        TemplateAction::Run
+                    } else {
+                        TemplateAction::Copy
+                    };
+                    Some(match action {
+                        _ if template::is_template(&value) => {
+                            Event::Command(Command::FillTemplate(id, action))
+                        }
+                        TemplateAction::Run => run_or_confirm(id, value),
+                        TemplateAction::Copy => Event::Command(Command::Copy(value)),
+                    })
+                })
+            }
+            KeyCode::Char(c) if c == keys.tags && matches!(ctx.ui.page, PageState::Dips { .. }) => {
+                Some(Event::Prompt(PromptEvent::TagFilter))
+            }
+            KeyCode::Esc if ctx.ui.page.get_search().is_some() => {
+                Some(Event::Prompt(PromptEvent::Search(SearchMode::Clear)))
+            }
+            KeyCode::Char(c) if c == keys.delete => match &ctx.ui.page {
                 PageState::Splash => None,
                 PageState::Dips {
                     focus,
@@ -921,9 +1568,15 @@ impl EventService {
                 } => match focus {
                     DipsFocus::Scope => None,
                     DipsFocus::List => match items.get(*index) {
-                        Some(id) => Some(Event::Prompt(PromptEvent::Confirm(Command::DeleteDip(
-                            id.clone(),
-                        )))),
+                        Some(id) if ctx.is_read_only(id) => {
+                            Some(Event::Prompt(PromptEvent::Message {
+                                msg: "Dips from the repository file are read-only",
+                                style: PromptStyle::Danger,
+                            }))
+                        }
+                        Some(id) => {
+                            Some(Event::Prompt(PromptEvent::Confirm(Command::DeleteDip(*id))))
+                        }
                         None => None,
                     },
                 },
@@ -932,10 +1585,7 @@ impl EventService {
             },
             KeyCode::Enter => match &ctx.ui.page {
                 PageState::Dips { focus, .. } => match focus {
-                    DipsFocus::List => Some(Event::Prompt(PromptEvent::Message {
-                        msg: "Enter is not implemented.",
-                        style: PromptStyle::Danger,
-                    })),
+                    DipsFocus::List => Some(Event::Action(Action::ToggleDetails)),
                     DipsFocus::Scope => Some(Event::Nav(PageType::Scopes)),
                 },
                 PageState::Scopes {
@@ -943,10 +1593,14 @@ impl EventService {
                     index,
                     focus,
                 } => match focus {
-                    ScopesFocus::Global => Some(Event::Nav(PageType::Dips { scope_id: None })),
+                    ScopesFocus::Global => Some(Event::Nav(PageType::Dips {
+                        scope_id: None,
+                        inherit: false,
+                    })),
                     ScopesFocus::List => match items.get(*index) {
                         Some(id) => Some(Event::Nav(PageType::Dips {
                             scope_id: Some(id.to_owned()),
+                            inherit: false,
                         })),
                         None => Some(Event::Prompt(PromptEvent::Message {
                             msg: "Could not determine the scope ID",
@@ -961,8 +1615,11 @@ impl EventService {
         }
     }
 
-    fn handle_prompt_events(event: &KeyEvent, _ctx: &AppState) -> Option<Event> {
+    fn handle_prompt_events(event: &KeyEvent, ctx: &AppState) -> Option<Event> {
         match event.code {
+            KeyCode::Esc if ctx.ui.prompt.get_search_input().is_some() => {
+                Some(Event::Prompt(PromptEvent::Search(SearchMode::Clear)))
+            }
             KeyCode::Esc => Some(Event::Prompt(PromptEvent::Defocus)),
             KeyCode::Backspace => Some(Event::Prompt(PromptEvent::InputBackspace)),
             KeyCode::Char(c) => Some(Event::Prompt(PromptEvent::Input(c))),
@@ -985,6 +1642,16 @@ impl EventService {
         }
     }
 
+    /// Stop reading the terminal events, so they don't get stolen from
+    /// the program the terminal is handed over to.
+    fn pause(&mut self) {
+        self.crossterm_events = None;
+    }
+
+    fn resume(&mut self) {
+        self.crossterm_events = Some(EventStream::new());
+    }
+
     fn send(&self, event: Event) {
         if self.dispatcher.send(event).is_err() {
             eprintln!("Failed to dispatch an event");
@@ -995,7 +1662,7 @@ impl EventService {
         loop {
             let ev = tokio::select! {
                 event = self.events.recv() => event,
-                event = self.crossterm_events.next() => match event {
+                event = next_terminal_event(&mut self.crossterm_events) => match event {
                     Some(Ok(ev)) => {
                     match ev {
                         CrosstermEvent::Key(key_event) if key_event.kind == KeyEventKind::Press => {
@@ -1019,11 +1686,29 @@ impl EventService {
 struct QueryManager {
     db_pool: SqlitePool,
     sender: mpsc::UnboundedSender<Event>,
+    /// The scopes the directory the app started in inherits from.
+    scope_chain: Vec<ScopeLink>,
+    /// The git repository root to read the repository file from.
+    git_root: Option<PathBuf>,
+    /// The checked out branch, the dips of the other branches are hidden.
+    branch: Option<String>,
 }
 
 impl QueryManager {
-    fn new(db_pool: SqlitePool, sender: mpsc::UnboundedSender<Event>) -> Self {
-        Self { db_pool, sender }
+    fn new(
+        db_pool: SqlitePool,
+        sender: mpsc::UnboundedSender<Event>,
+        scope_chain: Vec<ScopeLink>,
+        git_root: Option<PathBuf>,
+        branch: Option<String>,
+    ) -> Self {
+        Self {
+            db_pool,
+            sender,
+            scope_chain,
+            git_root,
+            branch,
+        }
     }
     // fn tag_dip(&self, state: &AppState) {
     //     let item = state
@@ -1076,18 +1761,24 @@ impl QueryManager {
 pub async fn exec(config: configuration::Application) -> color_eyre::Result<()> {
     tui::install_hooks()?;
     let mut terminal = tui::init()?;
-    let mut app_state = AppState::new();
+    let mut app_state = AppState::new(&config.settings);
     let (tx, rx) = mpsc::unbounded_channel();
     let mut events = EventService::new(rx, tx.clone());
-    let scope = dir_context::get_closest(&config.db_pool, &config.context_dir)
+    let depth = config.settings.user.depth;
+    let scope_chain = dir_context::get_scope_chain(&config.db_pool, &config.context_dir, depth)
         .await
         .expect("Failed to get dir context");
-    let query_mgr = QueryManager::new(config.db_pool, tx.clone());
+    let scope = scope_chain.first().map(|x| x.scope.clone());
+    let git_root = config.context_dir.git_dir_path().map(PathBuf::from);
+    let branch = config.context_dir.branch().map(String::from);
+    let query_mgr = QueryManager::new(config.db_pool, tx.clone(), scope_chain, git_root, branch);
+    let context_dir = config.context_dir;
 
     events.send(Event::Nav(PageType::Dips {
-        scope_id: scope.as_ref().map(|x| x.id.clone()),
+        scope_id: scope.as_ref().and_then(|x| x.id()),
+        inherit: true,
     }));
-    if let Some(scope) = scope {
+    if let Some(ContextScope::Dir(scope)) = scope {
         events.send(Event::LoadData(DataPayload::Scopes(vec![scope])))
     }
 
@@ -1103,18 +1794,33 @@ pub async fn exec(config: configuration::Application) -> color_eyre::Result<()>
         match events.next(&app_state).await? {
             Event::QuitSignal => app_state.mode = Mode::Quit,
             Event::LoadData(result) => match result {
-                DataPayload::Dips(items) => {
-                    app_state.load_dips_page(items);
+                DataPayload::Dips { search, items } => {
+                    if search.as_deref() == app_state.ui.page.get_search() {
+                        app_state.load_dips_page(items);
+                    }
                 }
                 DataPayload::Scopes(items) => {
                     app_state.load_scopes_page(items);
                 }
             },
-            Event::RefetchData(_) => app_state.ui.page.fetch_data(&query_mgr),
+            Event::RefetchData(page) => {
+                if page == app_state.ui.page.page_type() {
+                    app_state.ui.page.fetch_data(&query_mgr);
+                }
+            }
             Event::UiTick => {}
             Event::Action(action) => match action {
                 Action::MoveUp => app_state.ui.page.action_move_up(),
                 Action::MoveDown => app_state.ui.page.action_move_down(),
+                Action::Search(value) => {
+                    app_state.ui.page.action_search(&value);
+                    app_state.ui.page.fetch_data(&query_mgr);
+                }
+                Action::ToggleDetails => app_state.ui.page.action_toggle_details(),
+                Action::FilterTags(expr) => {
+                    app_state.ui.page.action_filter_tags(expr);
+                    app_state.ui.page.fetch_data(&query_mgr);
+                }
             },
             Event::Prompt(action) => match action {
                 PromptEvent::Focus => {
@@ -1126,14 +1832,50 @@ pub async fn exec(config: configuration::Application) -> color_eyre::Result<()>
                     app_state.ui.event_focus = EventFocusMode::Page;
                     app_state.ui.prompt.activate_default_state();
                 }
-                PromptEvent::Search(_) => {
+                PromptEvent::Search(SearchMode::Init) => {
+                    let input = app_state.ui.page.get_search().unwrap_or_default();
                     app_state.ui.event_focus = EventFocusMode::Prompt;
-                    app_state.ui.prompt.activate_search_state();
+                    app_state.ui.prompt.activate_search_state(input.to_owned());
+                }
+                PromptEvent::Search(SearchMode::Clear) => {
+                    app_state.ui.event_focus = EventFocusMode::Page;
+                    app_state.ui.prompt.activate_default_state();
+                    events.send(Event::Action(Action::Search(String::new())));
+                }
+                PromptEvent::TagFilter => {
+                    let input = app_state
+                        .ui
+                        .page
+                        .get_tags()
+                        .map(|x| x.to_string())
+                        .unwrap_or_default();
+                    app_state.ui.event_focus = EventFocusMode::Prompt;
+                    app_state.ui.prompt.activate_tag_filter_state(input);
+                }
+                PromptEvent::Edit(id) => {
+                    if let Some(item) = app_state.data.dips.get(&id) {
+                        let value = item.value.to_owned();
+                        app_state.ui.event_focus = EventFocusMode::Prompt;
+                        app_state.ui.prompt.activate_edit_state(id, value);
+                    }
+                }
+                PromptEvent::Template { id, action, last } => {
+                    if let Some(item) = app_state.data.dips.get(&id) {
+                        let placeholders = template::placeholders(&item.value);
+                        app_state.ui.event_focus = EventFocusMode::Prompt;
+                        app_state
+                            .ui
+                            .prompt
+                            .activate_template_state(id, action, placeholders, last);
+                    }
                 }
                 PromptEvent::Input(c) => {
                     if !app_state.ui.prompt.set_input(c) {
                         app_state.ui.prompt.set_error("Can not type in this mode");
                     }
+                    if let Some(input) = app_state.ui.prompt.get_search_input() {
+                        events.send(Event::Action(Action::Search(input.to_owned())));
+                    }
                 }
                 PromptEvent::Confirm(cmd) => {
                     app_state.ui.event_focus = EventFocusMode::Prompt;
@@ -1141,9 +1883,16 @@ pub async fn exec(config: configuration::Application) -> color_eyre::Result<()>
                 }
                 PromptEvent::InputBackspace => {
                     app_state.ui.prompt.set_input_backspace();
+                    if let Some(input) = app_state.ui.prompt.get_search_input() {
+                        events.send(Event::Action(Action::Search(input.to_owned())));
+                    }
                 }
                 PromptEvent::Commit => {
                     app_state.ui.prompt.handle_commit(&events.dispatcher);
+                    // Give the control back to the list while the search stays in place.
+                    if app_state.ui.prompt.is_search_committed() {
+                        app_state.ui.event_focus = EventFocusMode::Page;
+                    }
                 }
                 PromptEvent::Message { msg, style } => {
                     app_state.ui.prompt.handle_message(msg, style);
@@ -1154,6 +1903,98 @@ pub async fn exec(config: configuration::Application) -> color_eyre::Result<()>
                 Command::DeleteDip(id) => {
                     handle_delete_dip_command(&mut app_state.ui, &query_mgr, id)
                 }
+                Command::EditDip(id, value) => {
+                    handle_edit_dip_command(&mut app_state.ui, &query_mgr, id, value)
+                }
+                Command::EditNote(id) => {
+                    let note = app_state.data.dips.get(&id).and_then(|x| x.note.clone());
+                    // The editor needs the terminal, so we hand it over until it's closed.
+                    events.pause();
+                    tui::restore()?;
+                    let res = editor::edit_text(
+                        &app_state.editor,
+                        "note.md",
+                        note.as_deref().unwrap_or_default(),
+                    );
+                    terminal = tui::init()?;
+                    terminal.clear()?;
+                    events.resume();
+                    match res {
+                        Ok(text) => {
+                            if let Some(edited) = edit::note_change(note.as_deref(), &text) {
+                                handle_set_note_command(&app_state.ui, &query_mgr, id, edited);
+                            }
+                        }
+                        Err(_) => app_state.ui.prompt.set_error("Failed to open the editor"),
+                    }
+                }
+                Command::Run(id, value) => {
+                    let dir = match app_state.data.dips.get(&id) {
+                        Some(item) => {
+                            let scope = query_mgr.scope_chain.iter().find_map(|x| match &x.scope {
+                                ContextScope::Dir(dir)
+                                    if item.dir_context_id == Some(dir.id.to_string()) =>
+                                {
+                                    Some(dir)
+                                }
+                                _ => None,
+                            });
+                            run::run_dir(&context_dir, item, scope)
+                        }
+                        None => context_dir.dir().to_path_buf(),
+                    };
+                    // The command needs the terminal, so we hand it over until it's done.
+                    events.pause();
+                    tui::restore()?;
+                    println!("$ {value}");
+                    let res = run::run_value(&value, &dir);
+                    print!("\nPress Enter to return to dips");
+                    std::io::stdout().flush()?;
+                    std::io::stdin().read_line(&mut String::new())?;
+                    terminal = tui::init()?;
+                    terminal.clear()?;
+                    events.resume();
+                    match res {
+                        Ok(status) if status.success() => app_state
+                            .ui
+                            .prompt
+                            .handle_message(run::describe(&status), PromptStyle::Info),
+                        Ok(status) => app_state
+                            .ui
+                            .prompt
+                            .handle_message(run::describe(&status), PromptStyle::Danger),
+                        Err(_) => app_state.ui.prompt.set_error("Failed to run the command"),
+                    }
+                }
+                Command::Copy(value) => match clipboard::copy(&value, app_state.clipboard) {
+                    Ok(_) => app_state
+                        .ui
+                        .prompt
+                        .handle_message("Copied to the clipboard", PromptStyle::Info),
+                    Err(_) => app_state
+                        .ui
+                        .prompt
+                        .set_error("Failed to copy to the clipboard"),
+                },
+                Command::FillTemplate(id, action) => {
+                    let scope = app_state
+                        .data
+                        .dips
+                        .get(&id)
+                        .and_then(|x| x.dir_context_id.clone());
+                    handle_fill_template_command(&query_mgr, id, action, scope);
+                }
+                Command::TemplateFilled { id, action, values } => {
+                    let Some(item) = app_state.data.dips.get(&id) else {
+                        continue;
+                    };
+                    let value = template::render(&item.value, &values);
+                    handle_save_template_command(&query_mgr, item.dir_context_id.clone(), values);
+                    events.send(match action {
+                        TemplateAction::Run => run_or_confirm(id, value),
+                        TemplateAction::Copy => Event::Command(Command::Copy(value)),
+                    });
+                }
             },
             Event::Nav(page) => {
                 app_state.ui.navigate(&page);
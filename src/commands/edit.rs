@@ -0,0 +1,43 @@
+use crate::commands::{note as notes, select};
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::dip;
+
+/// The value typed in the TUI prompt without the surrounding whitespace,
+/// none when nothing is left of it.
+pub fn parse_value(input: &str) -> Option<&str> {
+    Some(input.trim()).filter(|x| !x.is_empty())
+}
+
+/// The note to store after editing it in $EDITOR, none when it's unchanged.
+/// `Some(None)` removes the note.
+pub fn note_change(current: Option<&str>, text: &str) -> Option<Option<String>> {
+    let edited = notes::normalize(text);
+    (edited.as_deref() != current).then_some(edited)
+}
+
+pub async fn exec(
+    app: &Application,
+    selector: &str,
+    value: Option<&str>,
+    note: Option<&str>,
+) -> Result<(), DipsError> {
+    if value.is_none() && note.is_none() {
+        return Err(DipsError::validation(
+            "Nothing to edit. Provide --value or --note",
+        ));
+    }
+
+    let item = select::resolve(app, selector).await?;
+    // An empty note removes it, like in `dips note`.
+    let note = note.map(notes::normalize);
+    dip::update(
+        &app.db_pool,
+        &item.id,
+        value,
+        note.as_ref().map(|x| x.as_deref()),
+    )
+    .await?;
+    println!("Dip {} updated.", value.unwrap_or(&item.value));
+    Ok(())
+}
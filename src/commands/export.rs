@@ -0,0 +1,186 @@
+use crate::commands::scope;
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::backup::{self, Backup, BackupSelection};
+use crate::models::dip;
+use crate::models::dir_context::{self, ContextScope};
+use crate::models::repo_file::{self, RepoDip};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Yaml,
+    /// Readable document grouped by the scope and the tag, it can't be imported
+    Md,
+}
+
+/// Write the dips of the scopes inside of the current git repository into the
+/// repository file, so they can be shared through git.
+async fn export_to_repo(app: &Application) -> Result<(), DipsError> {
+    let Some(git_root) = app.context_dir.git_dir_path() else {
+        return Err(DipsError::Git("Not inside a git repository".to_string()));
+    };
+
+    let chain = dir_context::get_scope_chain(&app.db_pool, &app.context_dir, None).await?;
+    let chain = chain
+        .into_iter()
+        .filter(|x| match &x.scope {
+            ContextScope::Dir(dir) => std::path::Path::new(&dir.dir_path).starts_with(git_root),
+            ContextScope::Global => false,
+        })
+        .collect::<Vec<_>>();
+    // The branch dips are personal reminders, they stay out of the shared file.
+    let filter = dip::DipsFilter::new().with_branch(None);
+    let items = dip::get_inherited(&app.db_pool, &chain, filter).await?;
+
+    let path = repo_file::path_or_default(git_root);
+    let mut file = if path.exists() {
+        repo_file::read(&path)?
+    } else {
+        repo_file::RepoFile::default()
+    };
+
+    let mut count = 0;
+    for item in items {
+        let added = file.push(RepoDip {
+            value: item.value,
+            note: item.note,
+            tags: item.tags.iter().map(|x| x.name.to_owned()).collect(),
+        });
+        if added {
+            count += 1;
+        }
+    }
+
+    repo_file::write(&path, &file)?;
+    println!("Exported {count} dips to {}.", path.display());
+    Ok(())
+}
+
+/// Keep the value on a single markdown line. The backticks inside of the
+/// value need a longer fence around it.
+fn md_code(value: &str) -> String {
+    let value = value.replace('\n', " ");
+    if value.contains('`') {
+        format!("`` {value} ``")
+    } else {
+        format!("`{value}`")
+    }
+}
+
+/// Render the backup as a markdown document with a section for every scope
+/// and a subsection for every tag. A dip with more tags is listed under each
+/// of them, the global scope and the untagged dips come last.
+pub fn render_markdown(backup: &Backup) -> String {
+    let tag_names = backup
+        .tags
+        .iter()
+        .map(|x| (x.id.as_str(), x.name.as_str()))
+        .collect::<BTreeMap<_, _>>();
+    let scope_paths = backup
+        .dir_contexts
+        .iter()
+        .map(|x| (x.id.to_string(), x.dir_path.as_str()))
+        .collect::<BTreeMap<_, _>>();
+
+    // scope path -> tag name -> dips, `None` is the global scope or the
+    // untagged dips and it's moved to the end when printing.
+    let mut groups: BTreeMap<Option<&str>, BTreeMap<Option<&str>, Vec<&backup::BackupDip>>> =
+        BTreeMap::new();
+    for item in &backup.dips {
+        let scope = item
+            .dir_context_id
+            .as_deref()
+            .and_then(|x| scope_paths.get(x).copied());
+        let id = item.id.to_string();
+        let tags = backup
+            .dips_tags
+            .iter()
+            .filter(|x| x.dip_id == id)
+            .filter_map(|x| tag_names.get(x.tag_id.as_str()).copied())
+            .collect::<Vec<_>>();
+        let scope_group = groups.entry(scope).or_default();
+        if tags.is_empty() {
+            scope_group.entry(None).or_default().push(item);
+        }
+        for tag in tags {
+            scope_group.entry(Some(tag)).or_default().push(item);
+        }
+    }
+
+    let mut out = String::from("# Dips\n");
+    if groups.is_empty() {
+        out.push_str("\nNo items found.\n");
+    }
+    let last_none = |x: &Option<&str>| (x.is_none(), x.map(str::to_owned));
+    let mut scopes = groups.into_iter().collect::<Vec<_>>();
+    scopes.sort_by_key(|(scope, _)| last_none(scope));
+    for (scope, tags) in scopes {
+        out.push_str(&format!("\n## {}\n", scope.unwrap_or("Global")));
+        let mut tags = tags.into_iter().collect::<Vec<_>>();
+        tags.sort_by_key(|(tag, _)| last_none(tag));
+        for (tag, items) in tags {
+            out.push_str(&format!("\n### {}\n\n", tag.unwrap_or("Untagged")));
+            for item in items {
+                match item.note.as_deref() {
+                    Some(note) => out.push_str(&format!(
+                        "- {} - {}\n",
+                        md_code(&item.value),
+                        note.replace('\n', " ")
+                    )),
+                    None => out.push_str(&format!("- {}\n", md_code(&item.value))),
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Print the dips with their scopes and tags so they can be restored with
+/// `dips import`. Without any option it exports the closest scope.
+async fn export_backup(
+    app: &Application,
+    format: ExportFormat,
+    scope: Option<&Path>,
+    all: bool,
+) -> Result<(), DipsError> {
+    let selection = if all {
+        BackupSelection::All
+    } else if let Some(path) = scope {
+        BackupSelection::Scope(ContextScope::Dir(scope::find(app, path).await?))
+    } else {
+        let closest = dir_context::get_closest(&app.db_pool, &app.context_dir).await?;
+        BackupSelection::Scope(ContextScope::from(closest))
+    };
+
+    let backup = backup::get_backup(&app.db_pool, &selection).await?;
+    match format {
+        ExportFormat::Json => {
+            let out = serde_json::to_string_pretty(&backup).expect("The backup is valid json");
+            println!("{out}");
+        }
+        ExportFormat::Yaml => {
+            let out = serde_yaml::to_string(&backup).expect("The backup is valid yaml");
+            print!("{out}");
+        }
+        ExportFormat::Md => print!("{}", render_markdown(&backup)),
+    }
+    Ok(())
+}
+
+pub async fn exec(
+    app: &Application,
+    format: ExportFormat,
+    scope: Option<&Path>,
+    all: bool,
+    to_repo: bool,
+) -> Result<(), DipsError> {
+    if to_repo {
+        export_to_repo(app).await
+    } else {
+        export_backup(app, format, scope, all).await
+    }
+}
@@ -1,38 +1,160 @@
+use crate::clipboard::{self, CopyMethod};
+use crate::commands::{select, template};
 use crate::configuration::Application;
+use crate::error::DipsError;
 use crate::models::dip;
-use crate::models::dir_context;
+use crate::models::dir_context::{self, ScopeLink, ScopeOrigin};
+use crate::models::repo_file;
+use crate::models::tag_expr::TagExpr;
+use std::io::Write;
 
-fn render_items(items: Vec<dip::DipRowFull>) {
-    if items.len() == 0 {
+/// The shape of the `dips get` output. Everything except `Plain` is meant
+/// to be consumed by scripts, so it does not print any decorations.
+#[derive(
+    clap::ValueEnum, serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Plain,
+    Json,
+    Yaml,
+    Tsv,
+    /// Values separated by the NUL character (for `xargs -0`)
+    Null,
+}
+
+static TSV_HEADER: &str =
+    "id\tvalue\tnote\ttags\tdir_path\tgit_remote\tgit_dir_name\tcreated_at\tupdated_at";
+
+/// Tabs and new lines would break the columns, so we escape them the same way
+/// the common tsv readers expect it.
+fn tsv_field(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn render_tsv(items: &[dip::DipRowFull]) -> String {
+    let mut out = String::from(TSV_HEADER);
+    out.push('\n');
+    for item in items {
+        let tags = item
+            .tags
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        let row = [
+            item.id.to_string(),
+            tsv_field(&item.value),
+            tsv_field(item.note.as_deref().unwrap_or_default()),
+            tsv_field(&tags),
+            tsv_field(item.dir_path.as_deref().unwrap_or_default()),
+            tsv_field(item.git_remote.as_deref().unwrap_or_default()),
+            tsv_field(item.git_dir_name.as_deref().unwrap_or_default()),
+            item.created_at.to_string(),
+            item.updated_at.to_string(),
+        ];
+        out.push_str(&row.join("\t"));
+        out.push('\n');
+    }
+    out
+}
+
+/// The dips in the format, the plain one only lists the values.
+pub fn render(items: &[dip::DipRowFull], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Plain if items.is_empty() => "No items found.\n".to_string(),
+        OutputFormat::Plain => items.iter().map(|x| format!("{}\n", x.value)).collect(),
+        OutputFormat::Json => {
+            let out = serde_json::to_string_pretty(items).expect("The dips are valid json");
+            format!("{out}\n")
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(items).expect("The dips are valid yaml"),
+        OutputFormat::Tsv => render_tsv(items),
+        OutputFormat::Null => items.iter().map(|x| format!("{}\0", x.value)).collect(),
+    }
+}
+
+fn render_items(items: Vec<dip::DipRowFull>, format: OutputFormat) -> Result<(), DipsError> {
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(render(&items, format).as_bytes())?;
+    Ok(())
+}
+
+/// Print the dips of every scope under its own header. The items come
+/// grouped by their origin, so we only print the header when it changes.
+/// The scopes without any dips are left out.
+fn render_grouped(chain: &[ScopeLink], items: Vec<dip::DipRowFull>) {
+    if items.is_empty() {
+        let label = chain.first().map(|x| x.scope.label()).unwrap_or_default();
+        println!("Scope: {label}");
         println!("No items found.");
-    } else {
-        for item in items {
-            println!("{}", item.value);
+        return;
+    }
+
+    let mut group = None;
+    for item in items {
+        let label = item.dir_path.as_deref().unwrap_or("Global").to_owned();
+        if group.as_ref() != Some(&(item.origin, label.clone())) {
+            match item.origin {
+                Some(ScopeOrigin::Global) | None => println!("Scope: {label}"),
+                Some(origin) => println!("Scope: {label} ({})", origin.label()),
+            }
+            group = Some((item.origin, label));
         }
+        println!("{}", item.value);
     }
 }
 
-pub async fn exec(app: &Application, all: bool) {
+pub async fn exec(
+    app: &Application,
+    all: bool,
+    format: OutputFormat,
+    depth: Option<usize>,
+    tags: Vec<TagExpr>,
+) -> Result<(), DipsError> {
+    let filter = dip::DipsFilter::new().with_tags(TagExpr::all(tags));
     if all {
-        let items = dip::get_all(&app.db_pool)
-            .await
-            .expect("Failed to read from database");
-        render_items(items);
+        let items = dip::get_filtered(&app.db_pool, filter.with_any_scope()).await?;
+        render_items(items, format)
     } else {
-        let scope = dir_context::get_closest(&app.db_pool, &app.context_dir)
-            .await
-            .expect("Failed to query dir context");
-        let filter = dip::DipsFilter::new().with_scope_id(scope.as_ref().map(|x| x.id.clone()));
-        let items = dip::get_filtered(&app.db_pool, filter)
-            .await
-            .expect("Failed to read from database");
-        println!(
-            "Scope: {}",
-            scope
-                .as_ref()
-                .map(|x| x.dir_path.as_str())
-                .unwrap_or("Global")
-        );
-        render_items(items);
+        let chain = dir_context::get_scope_chain(&app.db_pool, &app.context_dir, depth).await?;
+        let filter = filter.with_branch(app.context_dir.branch());
+        let mut items = dip::get_inherited(&app.db_pool, &chain, filter.clone()).await?;
+        match repo_file::get_rows(app.context_dir.git_dir_path()) {
+            Ok(rows) => {
+                let rows = rows
+                    .into_iter()
+                    .filter(|x| filter.tags().is_none_or(|expr| x.tags.matches(expr)))
+                    .collect();
+                repo_file::merge_rows(&mut items, rows);
+            }
+            Err(e) => eprintln!("ERROR: {e}"),
+        }
+        if format == OutputFormat::Plain {
+            render_grouped(&chain, items);
+            Ok(())
+        } else {
+            render_items(items, format)
+        }
+    }
+}
+
+/// Copy the value of the selected dip instead of printing the list.
+pub async fn copy(app: &Application, selector: &str) -> Result<(), DipsError> {
+    let item = select::resolve(app, selector).await?;
+    let value = template::fill(app, &item).await?;
+    match clipboard::copy(&value, app.settings.user.clipboard) {
+        Ok(CopyMethod::System) => println!("Copied {value} to the clipboard."),
+        Ok(CopyMethod::Terminal) => println!("Copied {value} to the clipboard via the terminal."),
+        Err(e) => {
+            let message = format!("Failed to copy {value}: {e}");
+            return Err(std::io::Error::new(e.kind(), message).into());
+        }
     }
+    Ok(())
 }
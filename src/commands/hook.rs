@@ -0,0 +1,138 @@
+use crate::configuration::Application;
+use crate::models::dip::{self, DipRowFull, DipsFilter};
+use crate::models::dir_context::{self, DirContext};
+use sqlx::types::Uuid;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How long we stay quiet about a scope after its summary was printed.
+const THROTTLE_SECS: i64 = 5 * 60;
+
+/// The state files of the sessions untouched for this long are removed, as
+/// their shells are most likely gone.
+const STALE_STATE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How many values the summary lists before it only counts the rest.
+const SUMMARY_LIMIT: usize = 5;
+
+/// What the hook remembers between the calls from the same shell session.
+#[derive(serde::Serialize, serde::Deserialize, Default, Debug)]
+pub struct HookState {
+    current: Option<Uuid>,
+    shown: HashMap<Uuid, i64>,
+}
+
+impl HookState {
+    /// Move the session to the scope and tell whether its summary is due, it
+    /// isn't while staying in the same scope or shortly after it was shown.
+    pub fn enter(&mut self, scope: Option<Uuid>, now: i64) -> bool {
+        if self.current == scope {
+            return false;
+        }
+        self.current = scope;
+        self.shown
+            .retain(|_, shown_at| now - *shown_at < THROTTLE_SECS);
+        scope.is_some_and(|x| !self.shown.contains_key(&x))
+    }
+
+    pub fn mark_shown(&mut self, scope: Uuid, now: i64) {
+        self.shown.insert(scope, now);
+    }
+}
+
+fn state_path(session: &str) -> Option<PathBuf> {
+    let name = format!("hook-{session}.json");
+    Some(dirs::cache_dir()?.join("dips").join(name))
+}
+
+/// Remove the state files of the sessions not seen for a while.
+pub fn prune_states(dir: &Path, max_age: Duration) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !name.starts_with("hook-") || !name.ends_with(".json") {
+            continue;
+        }
+        let stale = entry
+            .metadata()
+            .and_then(|x| x.modified())
+            .is_ok_and(|x| now.duration_since(x).unwrap_or_default() > max_age);
+        if stale {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+fn load_state(path: Option<&PathBuf>) -> HookState {
+    path.and_then(|x| std::fs::read(x).ok())
+        .and_then(|x| serde_json::from_slice(&x).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(path: Option<&PathBuf>, state: &HookState) {
+    let Some(path) = path else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(content) = serde_json::to_vec(state) {
+        let _ = std::fs::write(path, content);
+    }
+}
+
+/// A few lines about the dips of the scope, short enough to not get in the
+/// way of the prompt.
+pub fn summary(scope: &DirContext, items: &[DipRowFull]) -> String {
+    let mut out = format!("dips: {} in {}\n", items.len(), scope.dir_path);
+    for item in items.iter().take(SUMMARY_LIMIT) {
+        let value = item.value.lines().next().unwrap_or_default();
+        out.push_str(&format!("  {value}\n"));
+    }
+    if items.len() > SUMMARY_LIMIT {
+        let rest = items.len() - SUMMARY_LIMIT;
+        out.push_str(&format!("  ... and {rest} more, see `dips get`\n"));
+    }
+    out
+}
+
+/// Print the summary of the closest scope when the shell enters it. The
+/// session keeps the same scope from being printed again while moving around
+/// in it, or when coming back to it shortly after. Any failure is ignored, as
+/// the hook must never get in the way of the shell.
+pub async fn exec(app: &Application, session: Option<&str>) {
+    let path = session.and_then(state_path);
+    // A new session is a good time to clean up after the closed ones.
+    if let Some(dir) = path
+        .as_ref()
+        .filter(|x| !x.exists())
+        .and_then(|x| x.parent())
+    {
+        prune_states(dir, STALE_STATE);
+    }
+    let mut state = load_state(path.as_ref());
+    let scope = dir_context::get_closest(&app.db_pool, &app.context_dir)
+        .await
+        .ok()
+        .flatten();
+    let now = chrono::Utc::now().timestamp();
+    let due = state.enter(scope.as_ref().map(|x| x.id), now);
+    if let Some(scope) = scope.filter(|_| due) {
+        let filter = DipsFilter::new()
+            .with_scope_id(Some(scope.id))
+            .with_branch(app.context_dir.branch());
+        let items = dip::get_filtered(&app.db_pool, filter)
+            .await
+            .unwrap_or_default();
+        if !items.is_empty() {
+            print!("{}", summary(&scope, &items));
+            state.mark_shown(scope.id, now);
+        }
+    }
+    save_state(path.as_ref(), &state);
+}
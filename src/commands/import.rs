@@ -0,0 +1,109 @@
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::backup::{self, ImportMode};
+use crate::models::repo_file;
+use crate::models::{dip, dir_context, tag};
+use std::path::Path;
+
+/// Copy the dips from the repository file into the local database under the
+/// closest scope of the repository, or a new one for its root. The values
+/// already in the scope are skipped.
+async fn import_from_repo(app: &Application) -> Result<(), DipsError> {
+    let ctx = &app.context_dir;
+    let Some(git_root) = ctx.git_dir_path() else {
+        return Err(DipsError::validation("No repository file found"));
+    };
+    let Some(path) = repo_file::find(git_root) else {
+        return Err(DipsError::validation("No repository file found"));
+    };
+    let file = repo_file::read(&path)?;
+
+    let closest = dir_context::get_closest_in_repo(&app.db_pool, ctx).await?;
+    let mut tx = app.db_pool.begin().await?;
+    let current_dir_context = match closest {
+        Some(scope) => scope,
+        None => {
+            dir_context::db_find_or_create(
+                &mut tx,
+                &git_root.to_string_lossy(),
+                "",
+                ctx.git_dir().map(String::from),
+                ctx.git_remote().map(String::from),
+                ctx.match_dir_name(),
+            )
+            .await?
+        }
+    };
+
+    let mut count = 0;
+    for item in file.dips {
+        // Checked in the transaction, so the values repeated in the file
+        // are only added once.
+        let scope_id = Some(&current_dir_context.id);
+        if dip::value_exists(&mut *tx, &item.value, scope_id).await? {
+            continue;
+        }
+        let created = dip::create_with_transaction(
+            &mut tx,
+            Some(current_dir_context.id),
+            &item.value,
+            item.note.as_deref(),
+        )
+        .await?;
+        for name in tag::split_names(&item.tags) {
+            tag::create_dip_tag(&mut tx, &created.id, &name).await?;
+        }
+        count += 1;
+    }
+
+    tx.commit().await?;
+    println!("Imported {count} dips from {}.", path.display());
+    Ok(())
+}
+
+/// Restore the backup made by `dips export`. The dry run does all the work
+/// in the transaction and rolls it back at the end.
+async fn import_backup(
+    app: &Application,
+    path: &Path,
+    mode: ImportMode,
+    dry_run: bool,
+) -> Result<(), DipsError> {
+    let backup = backup::read(path)?;
+
+    let mut tx = app.db_pool.begin().await?;
+    let summary = backup::restore(&mut tx, &backup, mode).await?;
+
+    let prefix = if dry_run {
+        tx.rollback().await?;
+        "Would import"
+    } else {
+        tx.commit().await?;
+        "Imported"
+    };
+    println!(
+        "{prefix} {} dips, {} scopes and {} tags from {}, skipped {} existing dips.",
+        summary.dips,
+        summary.dir_contexts,
+        summary.tags,
+        path.display(),
+        summary.skipped
+    );
+    Ok(())
+}
+
+pub async fn exec(
+    app: &Application,
+    file: Option<&Path>,
+    mode: ImportMode,
+    dry_run: bool,
+    from_repo: bool,
+) -> Result<(), DipsError> {
+    if from_repo {
+        import_from_repo(app).await
+    } else if let Some(path) = file {
+        import_backup(app, path, mode, dry_run).await
+    } else {
+        Ok(())
+    }
+}
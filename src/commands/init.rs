@@ -1,19 +1,24 @@
 use crate::configuration::{Application, Settings};
+use crate::error::DipsError;
 use std::path::Path;
 
-pub async fn init(settings: Settings) {
+pub async fn init(settings: Settings) -> Result<(), DipsError> {
     let db_path = Path::new(&settings.database.path);
     if db_path.parent().is_none() {
-        panic!("Failed to resolve the parent directory for the database.");
+        return Err(DipsError::validation(
+            "Failed to resolve the parent directory for the database",
+        ));
     }
 
     if !db_path.exists() {
-        std::fs::File::create(db_path).expect("Failed to create database file.");
+        std::fs::File::create(db_path).map_err(|e| {
+            let message = format!("Failed to create {}: {e}", db_path.display());
+            std::io::Error::new(e.kind(), message)
+        })?;
     }
 
-    Application::build(settings)
-        .await
-        .expect("Failed to build the app with new database");
+    Application::build(settings).await?;
 
     println!("Dips got initialized.");
+    Ok(())
 }
@@ -1,4 +1,21 @@
 pub mod add;
+pub mod branch_gc;
+pub mod completions;
+pub mod config;
 pub mod core;
+pub mod edit;
+pub mod export;
 pub mod get;
+pub mod hook;
+pub mod import;
 pub mod init;
+pub mod note;
+pub mod rm;
+pub mod run;
+pub mod scope;
+pub mod search;
+pub mod select;
+pub mod shell_init;
+pub mod tag;
+pub mod tags;
+pub mod template;
@@ -0,0 +1,36 @@
+use crate::commands::select;
+use crate::configuration::Application;
+use crate::editor;
+use crate::error::DipsError;
+use crate::models::dip;
+
+/// The editors leave a new line at the end of the file, so we don't keep
+/// the trailing whitespace. An empty note removes it.
+pub fn normalize(text: &str) -> Option<String> {
+    let text = text.trim_end();
+    if text.trim().is_empty() {
+        None
+    } else {
+        Some(text.to_owned())
+    }
+}
+
+pub async fn exec(app: &Application, selector: &str) -> Result<(), DipsError> {
+    let item = select::resolve(app, selector).await?;
+    let editor = app.settings.editor();
+    let current = item.note.as_deref().unwrap_or_default();
+    let text = editor::edit_text(&editor, "note.md", current)
+        .map_err(|e| std::io::Error::new(e.kind(), format!("Failed to edit the note: {e}")))?;
+
+    let note = normalize(&text);
+    if note == item.note {
+        println!("Note of {} unchanged.", item.value);
+        return Ok(());
+    }
+    dip::update(&app.db_pool, &item.id, None, Some(note.as_deref())).await?;
+    match note {
+        Some(_) => println!("Note of {} updated.", item.value),
+        None => println!("Note of {} removed.", item.value),
+    }
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::commands::select::{self, SelectorMatch};
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::dip;
+use std::io::{IsTerminal, Write};
+
+/// The fuzzy match might not be the dip the user had in mind, so it has to
+/// be confirmed first. Without a terminal there is nobody to ask.
+fn confirm(selector: &str, value: &str) -> Result<(), DipsError> {
+    if !std::io::stdin().is_terminal() {
+        return Err(DipsError::validation(format!(
+            "\"{selector}\" only fuzzy matches {value}, use --yes, its id or the exact value to remove it"
+        )));
+    }
+    print!("Remove {value}? (y/N) ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim().eq_ignore_ascii_case("y") {
+        Ok(())
+    } else {
+        Err(DipsError::Cancelled)
+    }
+}
+
+pub async fn exec(app: &Application, selector: &str, yes: bool) -> Result<(), DipsError> {
+    let (item, matched) = select::resolve_match(app, selector).await?;
+    if matched == SelectorMatch::Fuzzy && !yes {
+        confirm(selector, &item.value)?;
+    }
+    dip::delete(&app.db_pool, &item.id).await?;
+    println!("Dip {} removed.", item.value);
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use crate::commands::{select, template};
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::dip::DipRowFull;
+use crate::models::dir_context::{self, DirContext, RuntimeDirContext};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+/// Parts of the commands that can destroy data or are hard to take back.
+/// They are matched against the value with its whitespace collapsed.
+const DANGEROUS_PATTERNS: &[&str] = &[
+    "rm -r",
+    "rm -f",
+    "sudo ",
+    "mkfs",
+    "dd if=",
+    "> /dev/",
+    "chmod -r",
+    "chown -r",
+    "drop table",
+    "drop database",
+    "truncate ",
+    "git push -f",
+    "git push --force",
+    "git reset --hard",
+    "git clean",
+    "kubectl delete",
+    "docker system prune",
+    "shutdown",
+    "reboot",
+    ":(){",
+];
+
+/// Whether the value looks like a command we should ask about before running.
+pub fn is_dangerous(value: &str) -> bool {
+    let value = value
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    DANGEROUS_PATTERNS.iter().any(|x| value.contains(x))
+}
+
+/// The shell of the user, the commands in the dips are written for it.
+pub fn shell() -> String {
+    std::env::var("SHELL")
+        .ok()
+        .filter(|x| !x.trim().is_empty())
+        .unwrap_or_else(|| "sh".to_string())
+}
+
+/// Run the value with `$SHELL -c` in the directory and wait for it. The
+/// output goes straight to the terminal.
+pub fn run_value(value: &str, dir: &Path) -> std::io::Result<ExitStatus> {
+    Command::new(shell())
+        .arg("-c")
+        .arg(value)
+        .current_dir(dir)
+        .status()
+}
+
+/// The directory to run the dip in. The scopes shared by the clones of the
+/// repository run at their path inside of the clone we're in, as the clone
+/// they were added in may be elsewhere or gone. The other dips only run in
+/// their own directory when it contains the one we act on, in that one
+/// otherwise, like the global dips.
+pub fn run_dir(ctx: &RuntimeDirContext, item: &DipRowFull, scope: Option<&DirContext>) -> PathBuf {
+    let canonical_remote = ctx.canonical_remote();
+    if let (Some(scope), Some(git_root)) = (scope, ctx.git_dir_path()) {
+        if scope.canonical_remote.is_some() && scope.canonical_remote == canonical_remote {
+            return match scope.subpath.as_str() {
+                "" => git_root.to_path_buf(),
+                subpath => git_root.join(subpath),
+            };
+        }
+    }
+    item.dir_path
+        .as_deref()
+        .map(Path::new)
+        .filter(|x| ctx.dir().starts_with(x))
+        .unwrap_or(ctx.dir())
+        .to_path_buf()
+}
+
+/// Human readable outcome of the command.
+pub fn describe(status: &ExitStatus) -> String {
+    match status.code() {
+        Some(0) => "Command succeeded".to_string(),
+        Some(code) => format!("Command failed with exit code {code}"),
+        None => "Command was terminated by a signal".to_string(),
+    }
+}
+
+/// The code to exit with after running the command. A command killed by a
+/// signal gets 128 plus the signal, as in the shells.
+pub fn exit_code(status: &ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+fn confirm(value: &str) -> bool {
+    if !std::io::stdin().is_terminal() {
+        println!("{value} looks destructive. Use --yes to run it anyway.");
+        return false;
+    }
+    print!("{value} looks destructive. Run it anyway? (y/N) ");
+    if std::io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    answer.trim().eq_ignore_ascii_case("y")
+}
+
+/// Run the selected dip and return its exit status, so the caller can exit
+/// with the same code.
+pub async fn exec(app: &Application, selector: &str, yes: bool) -> Result<ExitStatus, DipsError> {
+    let item = select::resolve(app, selector).await?;
+    let value = template::fill(app, &item).await?;
+    if !yes && is_dangerous(&value) && !confirm(&value) {
+        return Err(DipsError::Cancelled);
+    }
+    let scope = match item.dir_context_id.as_deref() {
+        Some(id) => dir_context::find_by_id(&app.db_pool, id).await?,
+        None => None,
+    };
+    let dir = run_dir(&app.context_dir, &item, scope.as_ref());
+    let status = run_value(&value, &dir)
+        .map_err(|e| std::io::Error::new(e.kind(), format!("Failed to run {value}: {e}")))?;
+    if !status.success() {
+        eprintln!("{}.", describe(&status));
+    }
+    Ok(status)
+}
@@ -0,0 +1,167 @@
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::dip::{self, DipsFilter};
+use crate::models::dir_context::{self, DirContext, RuntimeDirContext};
+use std::path::{Path, PathBuf};
+
+/// What happens to the dips of the removed scope.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemoveMode {
+    /// Only remove the scope when it has no dips.
+    Empty,
+    /// Remove the dips with the scope.
+    Cascade,
+    /// Keep the dips as the global ones.
+    ReassignGlobal,
+}
+
+/// The scopes are stored with the absolute paths. The directories that were
+/// moved or removed can't be canonicalized, so those are only made absolute.
+pub fn absolute(path: &Path) -> String {
+    let path = path
+        .canonicalize()
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf());
+    path.components().collect::<PathBuf>().display().to_string()
+}
+
+/// Find the scope stored for the directory.
+pub async fn find(app: &Application, path: &Path) -> Result<DirContext, DipsError> {
+    let path = absolute(path);
+    dir_context::find_by_path(&app.db_pool, &path)
+        .await?
+        .ok_or(DipsError::ScopeNotFound(path))
+}
+
+/// Print every scope with the number of its dips.
+pub async fn list(app: &Application) -> Result<(), DipsError> {
+    let rows = dir_context::get_usage(&app.db_pool).await?;
+    if rows.is_empty() {
+        println!("No scopes found.");
+        return Ok(());
+    }
+    for row in rows {
+        match row.scope.git_remote {
+            Some(remote) => println!("{} ({}) {remote}", row.scope.dir_path, row.count),
+            None => println!("{} ({})", row.scope.dir_path, row.count),
+        }
+    }
+    Ok(())
+}
+
+/// Print the details and the dips of the scope, the closest one by default.
+pub async fn show(app: &Application, path: Option<&Path>) -> Result<(), DipsError> {
+    let scope = match path {
+        Some(path) => find(app, path).await?,
+        None => dir_context::get_closest(&app.db_pool, &app.context_dir)
+            .await?
+            .ok_or_else(|| DipsError::ScopeNotFound(app.context_dir.path()))?,
+    };
+    let filter = DipsFilter::new().with_scope_id(Some(scope.id));
+    let items = dip::get_filtered(&app.db_pool, filter).await?;
+
+    println!("Scope: {}", scope.dir_path);
+    println!("Id: {}", scope.id);
+    if let Some(remote) = &scope.git_remote {
+        println!("Git remote: {remote}");
+    }
+    if let Some(name) = &scope.git_dir_name {
+        println!("Git directory: {name}");
+    }
+    if !scope.subpath.is_empty() {
+        println!("Repository path: {}", scope.subpath);
+    }
+    println!("Created: {}", scope.created_at);
+    println!("Updated: {}", scope.updated_at);
+    println!("Dips: {}", items.len());
+    for item in items {
+        println!("  {}", item.value);
+    }
+    Ok(())
+}
+
+/// Point the scope, and the scopes nested in it, to the new path. Meant for
+/// the repositories that were moved on disk.
+pub async fn mv(app: &Application, old: &Path, new: &Path) -> Result<(), DipsError> {
+    let scope = find(app, old).await?;
+    let new_path = absolute(new);
+    let mut tx = app.db_pool.begin().await?;
+    if let Some(path) =
+        dir_context::find_relocate_conflict(&mut tx, &scope.dir_path, &new_path).await?
+    {
+        return Err(DipsError::validation(format!(
+            "There is a scope for {path} already"
+        )));
+    }
+    let moved = dir_context::relocate(&mut tx, &scope.dir_path, &new_path).await?;
+    for dir in &moved {
+        let context = RuntimeDirContext::try_from(Path::new(&dir.dir_path)).ok();
+        dir_context::set_git(&mut tx, &dir.id, context.as_ref()).await?;
+    }
+    tx.commit().await?;
+    match moved.len() {
+        1 => println!("Scope {} moved to {new_path}.", scope.dir_path),
+        _ => println!(
+            "Scope {} moved to {new_path} with {} nested scopes.",
+            scope.dir_path,
+            moved.len() - 1
+        ),
+    }
+    Ok(())
+}
+
+pub async fn rm(app: &Application, path: &Path, mode: RemoveMode) -> Result<(), DipsError> {
+    let scope = find(app, path).await?;
+    let mut tx = app.db_pool.begin().await?;
+    // The archived dips keep the scope too, even though they aren't listed.
+    let count = dir_context::count_dips(&mut tx, &scope.id).await?;
+    let message = match (mode, count) {
+        (_, 0) => format!("Scope {} removed.", scope.dir_path),
+        (RemoveMode::Empty, _) => {
+            return Err(DipsError::validation(format!(
+                "Scope {} has {count} dips. Use --cascade to remove them or --reassign-global to keep them as global dips",
+                scope.dir_path
+            )));
+        }
+        (RemoveMode::Cascade, _) => {
+            let removed = dir_context::delete_dips(&mut tx, &scope.id).await?;
+            format!("Scope {} removed with {removed} dips.", scope.dir_path)
+        }
+        (RemoveMode::ReassignGlobal, _) => {
+            let moved = dir_context::reassign_global(&mut tx, &scope.id).await?;
+            format!(
+                "Scope {} removed, its {moved} dips are global now.",
+                scope.dir_path
+            )
+        }
+    };
+    dir_context::delete(&mut tx, &scope.id).await?;
+    tx.commit().await?;
+    println!("{message}");
+    Ok(())
+}
+
+/// Remove the scopes without any dips whose directory no longer exists.
+pub async fn prune(app: &Application) -> Result<(), DipsError> {
+    let orphans = dir_context::get_usage(&app.db_pool)
+        .await?
+        .into_iter()
+        .filter(|x| x.count == 0 && !Path::new(&x.scope.dir_path).exists())
+        .map(|x| x.scope)
+        .collect::<Vec<_>>();
+    if orphans.is_empty() {
+        println!("No orphaned scopes found.");
+        return Ok(());
+    }
+
+    let mut tx = app.db_pool.begin().await?;
+    for scope in &orphans {
+        dir_context::delete(&mut tx, &scope.id).await?;
+    }
+    tx.commit().await?;
+    println!("Removed {} orphaned scopes:", orphans.len());
+    for scope in orphans {
+        println!("  {}", scope.dir_path);
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::dip::{self, HIGHLIGHT_END, HIGHLIGHT_START};
+use crate::models::dir_context;
+use std::io::IsTerminal;
+
+/// Replace the snippet highlight markers with bold text on the terminal and
+/// drop them when the output is piped somewhere else.
+fn render_snippet(snippet: &str, colored: bool) -> String {
+    let (start, end) = if colored {
+        ("\x1b[1m", "\x1b[22m")
+    } else {
+        ("", "")
+    };
+    snippet
+        .replace(HIGHLIGHT_START, start)
+        .replace(HIGHLIGHT_END, end)
+}
+
+pub async fn exec(app: &Application, query: &str, all: bool) -> Result<(), DipsError> {
+    let mut filter = dip::DipsFilter::new().with_search(query);
+    if all {
+        filter = filter.with_any_scope();
+    } else {
+        let scope = dir_context::get_closest(&app.db_pool, &app.context_dir).await?;
+        filter = filter
+            .with_scope_id(scope.as_ref().map(|x| x.id))
+            .with_branch(app.context_dir.branch());
+    }
+
+    let items = dip::get_filtered(&app.db_pool, filter).await?;
+
+    if items.is_empty() {
+        println!("No items found.");
+        return Ok(());
+    }
+
+    let colored = std::io::stdout().is_terminal();
+    for item in items {
+        let id = item.id.to_string();
+        println!("{} {}", &id[..8], item.value);
+        if let Some(snippet) = item.snippet.as_deref() {
+            let plain = render_snippet(snippet, false);
+            if plain != item.value {
+                println!("         {}", render_snippet(snippet, colored));
+            }
+        }
+    }
+    Ok(())
+}
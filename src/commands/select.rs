@@ -0,0 +1,110 @@
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::dip::{self, DipRowFull};
+use crate::models::dir_context;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::io::{IsTerminal, Write};
+
+/// Minimal length of the selector before we try to treat it as an id prefix.
+/// Anything shorter is too likely to collide with a plain value.
+const ID_PREFIX_MIN_LEN: usize = 4;
+
+fn is_id_prefix(selector: &str) -> bool {
+    selector.len() >= ID_PREFIX_MIN_LEN
+        && selector.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+}
+
+/// Find the dips whose value fuzzy matches the selector, ordered by the best
+/// match first. The TUI filters its list with it too.
+pub fn fuzzy_matches(items: Vec<DipRowFull>, selector: &str) -> Vec<DipRowFull> {
+    let matcher = SkimMatcherV2::default();
+    let mut scored = items
+        .into_iter()
+        .filter_map(|item| {
+            matcher
+                .fuzzy_match(&item.value, selector)
+                .map(|score| (score, item))
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Let the user pick one of the matching dips. When we are not attached to a
+/// terminal, we only print the list as there is nobody to answer.
+fn disambiguate(selector: &str, mut items: Vec<DipRowFull>) -> Result<DipRowFull, DipsError> {
+    println!("Multiple dips match \"{selector}\":");
+    for (i, item) in items.iter().enumerate() {
+        let id = item.id.to_string();
+        println!("  {}) {} ({})", i + 1, item.value, &id[..8]);
+    }
+
+    if !std::io::stdin().is_terminal() {
+        return Err(DipsError::AmbiguousSelector(selector.to_owned()));
+    }
+
+    print!("Select a number (empty to cancel): ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Err(DipsError::Cancelled);
+    }
+
+    match answer.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= items.len() => Ok(items.swap_remove(n - 1)),
+        _ => Err(DipsError::validation(format!(
+            "Invalid selection \"{answer}\""
+        ))),
+    }
+}
+
+/// How the selector matched the dip.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectorMatch {
+    Id,
+    Value,
+    Fuzzy,
+}
+
+fn pick_one(selector: &str, mut items: Vec<DipRowFull>) -> Result<DipRowFull, DipsError> {
+    match items.len() {
+        0 => Err(DipsError::DipNotFound(selector.to_owned())),
+        1 => Ok(items.pop().expect("There is exactly one item")),
+        _ => disambiguate(selector, items),
+    }
+}
+
+/// Resolve the selector to a single dip. The selector is tried as:
+/// - an id prefix (across all the scopes)
+/// - an exact value in the current scope chain
+/// - a fuzzy query in the current scope chain
+pub async fn resolve(app: &Application, selector: &str) -> Result<DipRowFull, DipsError> {
+    resolve_match(app, selector).await.map(|(item, _)| item)
+}
+
+/// Like `resolve`, and tell how the selector matched.
+pub async fn resolve_match(
+    app: &Application,
+    selector: &str,
+) -> Result<(DipRowFull, SelectorMatch), DipsError> {
+    if is_id_prefix(selector) {
+        let items = dip::get_by_id_prefix(&app.db_pool, selector).await?;
+        if !items.is_empty() {
+            return pick_one(selector, items).map(|x| (x, SelectorMatch::Id));
+        }
+    }
+
+    let chain = dir_context::get_scope_chain(&app.db_pool, &app.context_dir, None).await?;
+    let filter = dip::DipsFilter::new().with_branch(app.context_dir.branch());
+    let items = dip::get_inherited(&app.db_pool, &chain, filter).await?;
+
+    let (exact, rest): (Vec<_>, Vec<_>) = items.into_iter().partition(|x| x.value == selector);
+    if !exact.is_empty() {
+        return pick_one(selector, exact).map(|x| (x, SelectorMatch::Value));
+    }
+
+    pick_one(selector, fuzzy_matches(rest, selector)).map(|x| (x, SelectorMatch::Fuzzy))
+}
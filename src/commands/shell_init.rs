@@ -0,0 +1,50 @@
+/// The shells we can hook into.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum InitShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+// Bash has no hook for the directory change, so we compare the directory on
+// every prompt instead.
+static BASH: &str = r#"_dips_hook() {
+    local status=$?
+    if [[ "${_DIPS_LAST_PWD:-}" != "$PWD" ]]; then
+        _DIPS_LAST_PWD="$PWD"
+        command dips hook --session "$$"
+    fi
+    return $status
+}
+if [[ ";${PROMPT_COMMAND:-};" != *";_dips_hook;"* ]]; then
+    PROMPT_COMMAND="_dips_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
+"#;
+
+static ZSH: &str = r#"_dips_hook() {
+    command dips hook --session "$$"
+}
+autoload -Uz add-zsh-hook
+add-zsh-hook chpwd _dips_hook
+_dips_hook
+"#;
+
+static FISH: &str = r#"function __dips_hook --on-variable PWD
+    command dips hook --session $fish_pid
+end
+__dips_hook
+"#;
+
+pub fn script(shell: InitShell) -> &'static str {
+    match shell {
+        InitShell::Bash => BASH,
+        InitShell::Zsh => ZSH,
+        InitShell::Fish => FISH,
+    }
+}
+
+/// Print the script to be evaluated in the shell's rc file, e.g.
+/// `eval "$(dips shell-init bash)"`.
+pub fn exec(shell: InitShell) {
+    print!("{}", script(shell));
+}
@@ -0,0 +1,55 @@
+use crate::commands::select;
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::tag;
+
+/// Split the `+foo -bar` arguments into the tags to add and to remove. A name
+/// without a sign is added.
+fn parse_changes(changes: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut added = vec![];
+    let mut removed = vec![];
+    for change in changes {
+        match change.strip_prefix('-') {
+            Some(name) => removed.push(name.to_owned()),
+            None => added.push(change.strip_prefix('+').unwrap_or(change).to_owned()),
+        }
+    }
+    (tag::split_names(&added), tag::split_names(&removed))
+}
+
+pub async fn exec(app: &Application, selector: &str, changes: &[String]) -> Result<(), DipsError> {
+    let (added, removed) = parse_changes(changes);
+    if added.is_empty() && removed.is_empty() {
+        return Err(DipsError::validation(
+            "Nothing to change. Use +name to add a tag and -name to remove it",
+        ));
+    }
+
+    let item = select::resolve(app, selector).await?;
+    let mut names = item
+        .tags
+        .iter()
+        .map(|x| x.name.to_owned())
+        .collect::<Vec<_>>();
+
+    let mut tx = app.db_pool.begin().await?;
+    for name in removed {
+        tag::delete_dip_tag(&mut tx, &item.id, &name).await?;
+        names.retain(|x| *x != name);
+    }
+    for name in added {
+        if names.contains(&name) {
+            continue;
+        }
+        tag::create_dip_tag(&mut tx, &item.id, &name).await?;
+        names.push(name);
+    }
+    tx.commit().await?;
+
+    if names.is_empty() {
+        println!("Dip {} has no tags.", item.value);
+    } else {
+        println!("Dip {} tagged: {}.", item.value, names.join(", "));
+    }
+    Ok(())
+}
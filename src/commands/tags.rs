@@ -0,0 +1,113 @@
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::tag;
+use sqlx::{Sqlite, Transaction};
+
+async fn find_id(tx: &mut Transaction<'_, Sqlite>, name: &str) -> Result<tag::Id, DipsError> {
+    tag::find_id(tx, name)
+        .await?
+        .ok_or_else(|| DipsError::TagNotFound(name.to_owned()))
+}
+
+/// The new name of a tag without the surrounding whitespace. The commas
+/// separate the names in `--tag a,b`, so they can't be a part of one.
+fn new_name(name: &str) -> Result<&str, DipsError> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(',') {
+        return Err(DipsError::validation(format!(
+            "\"{name}\" is not a valid tag name, it must not be empty or contain a comma"
+        )));
+    }
+    Ok(name)
+}
+
+/// Print every tag with its total and the number of dips in each scope.
+pub async fn list(app: &Application) -> Result<(), DipsError> {
+    let rows = tag::get_usage(&app.db_pool).await?;
+    if rows.is_empty() {
+        println!("No tags found.");
+        return Ok(());
+    }
+
+    let mut index = 0;
+    while index < rows.len() {
+        let name = &rows[index].name;
+        let group = rows[index..]
+            .iter()
+            .take_while(|x| x.name == *name)
+            .collect::<Vec<_>>();
+        let total = group.iter().map(|x| x.count).sum::<i64>();
+        println!("{name} ({total})");
+        for row in group.iter().filter(|x| x.count > 0) {
+            let scope = match &row.dir_path {
+                Some(path) => path.as_str(),
+                None => "Global",
+            };
+            println!("  {scope} ({})", row.count);
+        }
+        index += group.len();
+    }
+    Ok(())
+}
+
+pub async fn rename(app: &Application, old: &str, new: &str) -> Result<(), DipsError> {
+    let new = new_name(new)?;
+    let mut tx = app.db_pool.begin().await?;
+    let id = find_id(&mut tx, old).await?;
+    if tag::find_id(&mut tx, new).await?.is_some() {
+        return Err(DipsError::validation(format!(
+            "Tag {new} already exists. Use `dips tags merge {old} {new}` instead"
+        )));
+    }
+    tag::rename(&mut tx, &id, new).await?;
+    tx.commit().await?;
+    println!("Tag {old} renamed to {new}.");
+    Ok(())
+}
+
+/// Move the dips of the first tag to the second one. Merging into a tag that
+/// doesn't exist is the same as renaming it.
+pub async fn merge(app: &Application, from: &str, into: &str) -> Result<(), DipsError> {
+    let into = new_name(into)?;
+    if from == into {
+        return Err(DipsError::validation(format!(
+            "Can't merge the tag {from} into itself"
+        )));
+    }
+    let mut tx = app.db_pool.begin().await?;
+    let from_id = find_id(&mut tx, from).await?;
+    match tag::find_id(&mut tx, into).await? {
+        Some(into_id) => {
+            let moved = tag::merge(&mut tx, &from_id, &into_id).await?;
+            tx.commit().await?;
+            println!("Tag {from} merged into {into}, {moved} dips retagged.");
+        }
+        None => {
+            tag::rename(&mut tx, &from_id, into).await?;
+            tx.commit().await?;
+            println!("Tag {from} renamed to {into}.");
+        }
+    }
+    Ok(())
+}
+
+pub async fn rm(app: &Application, name: &str) -> Result<(), DipsError> {
+    let mut tx = app.db_pool.begin().await?;
+    let id = find_id(&mut tx, name).await?;
+    let count = tag::delete(&mut tx, &id).await?;
+    tx.commit().await?;
+    println!("Tag {name} removed from {count} dips.");
+    Ok(())
+}
+
+pub async fn prune(app: &Application) -> Result<(), DipsError> {
+    let mut tx = app.db_pool.begin().await?;
+    let names = tag::prune(&mut tx).await?;
+    tx.commit().await?;
+    if names.is_empty() {
+        println!("No unused tags found.");
+    } else {
+        println!("Removed {} unused tags: {}.", names.len(), names.join(", "));
+    }
+    Ok(())
+}
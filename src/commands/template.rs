@@ -0,0 +1,48 @@
+use crate::configuration::Application;
+use crate::error::DipsError;
+use crate::models::dip::DipRowFull;
+use crate::models::template;
+use std::collections::HashMap;
+use std::io::Write;
+
+fn ask(name: &str, suggestion: Option<&str>) -> Option<String> {
+    match suggestion {
+        Some(suggestion) => print!("{name} [{suggestion}]: "),
+        None => print!("{name}: "),
+    }
+    std::io::stdout().flush().ok()?;
+    let mut answer = String::new();
+    let read = std::io::stdin().read_line(&mut answer).ok()?;
+    let answer = answer.trim_end_matches(['\n', '\r']);
+    match (read, answer, suggestion) {
+        (0, _, _) => suggestion.map(str::to_owned),
+        (_, "", Some(suggestion)) => Some(suggestion.to_owned()),
+        (_, "", None) => ask(name, None),
+        (_, answer, _) => Some(answer.to_owned()),
+    }
+}
+
+/// Ask for the placeholders of the dip on stdin and return the value to use.
+/// The answers are remembered for the next time in the scope of the dip.
+pub async fn fill(app: &Application, item: &DipRowFull) -> Result<String, DipsError> {
+    let placeholders = template::placeholders(&item.value);
+    if placeholders.is_empty() {
+        return Ok(item.value.to_owned());
+    }
+
+    let scope = item.dir_context_id.as_deref();
+    let last = template::get_last_values(&app.db_pool, scope).await?;
+    let mut values = HashMap::new();
+    for placeholder in placeholders {
+        let suggestion = template::suggestion(&placeholder, &last);
+        let Some(value) = ask(&placeholder.name, suggestion.as_deref()) else {
+            return Err(DipsError::validation(format!(
+                "No value for {}",
+                placeholder.name
+            )));
+        };
+        values.insert(placeholder.name, value);
+    }
+    template::save_values(&app.db_pool, scope, &values).await?;
+    Ok(template::render(&item.value, &values))
+}
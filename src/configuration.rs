@@ -1,14 +1,18 @@
-use crate::models::dir_context::RuntimeDirContext;
+use crate::clipboard::ClipboardBackend;
+use crate::commands::get::OutputFormat;
+use crate::editor;
+use crate::error::DipsError;
+use crate::models::dir_context::{self, RuntimeDirContext};
 use sqlx::SqlitePool;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-static DB_NAME: &'static str = "dips.db";
+static DB_NAME: &str = "dips.db";
 
-#[derive(thiserror::Error, Debug)]
-pub enum ConfigError {
-    #[error("Dips is not initialized yet")]
-    Uninitialized,
-}
+static CONFIG_FILE: &str = "config.toml";
+
+/// The prefix of the environment variables overriding the config file, e.g.
+/// `DIPS_FORMAT=json` or `DIPS_TUI__THEME=light`.
+static ENV_PREFIX: &str = "DIPS";
 
 /// Helper function to figure out what environment is the application currently running. It will
 /// create this value dynamically based on the compilation debug mode.
@@ -22,7 +26,7 @@ impl Environment {
     pub fn current() -> Self {
         #[cfg(debug_assertions)]
         {
-            return Environment::Development;
+            Environment::Development
         }
         #[cfg(not(debug_assertions))]
         {
@@ -32,18 +36,143 @@ impl Environment {
 }
 
 /// This holds the user configurations of the application.
-/// Right now, it holds hardcoded values as we don't support
-/// custom setup settings. As there is nothing to support yet.
 #[derive(Debug)]
 pub struct Settings {
     pub database: DatabaseSettings,
+    pub user: UserSettings,
+    /// The directory whose scope the commands act on, the current one
+    /// unless it's given with `-C`.
+    pub dir: Option<PathBuf>,
 }
 
 impl Settings {
-    /// Build the settings from possible different sources
+    /// Build the settings from possible different sources. A broken config
+    /// file shouldn't lock the user out, so we warn and use the defaults.
     pub fn build(env: &Environment) -> Self {
-        let database = DatabaseSettings::build(env);
-        Self { database }
+        let user = UserSettings::load().unwrap_or_else(|e| {
+            eprintln!("WARNING: Failed to load the config, using the defaults: {e}");
+            UserSettings::default()
+        });
+        let database = DatabaseSettings::build(env, user.db_path.as_deref());
+        Self {
+            database,
+            user,
+            dir: None,
+        }
+    }
+
+    /// The editor command, the config wins over `$VISUAL` and `$EDITOR`.
+    pub fn editor(&self) -> String {
+        self.user
+            .editor
+            .clone()
+            .filter(|x| !x.trim().is_empty())
+            .unwrap_or_else(editor::editor)
+    }
+}
+
+/// The path of the config file, `$XDG_CONFIG_HOME/dips/config.toml` or
+/// `~/.config/dips/config.toml`.
+pub fn config_path() -> PathBuf {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|x| !x.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            dirs::home_dir()
+                .expect("Failed to find home directory")
+                .join(".config")
+        });
+    dir.join("dips").join(CONFIG_FILE)
+}
+
+/// The settings the user can change in the config file or with the
+/// environment variables. Everything has a default, so the file can only
+/// list what's different.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct UserSettings {
+    /// Path of the database file, `~` stands for the home directory.
+    pub db_path: Option<String>,
+    /// The default format of `dips get`.
+    pub format: OutputFormat,
+    /// The default number of inherited scopes shown after the closest one.
+    pub depth: Option<usize>,
+    /// Treat the repositories without a remote as the same scope when their
+    /// directories have the same name.
+    pub match_dir_name: bool,
+    /// Inherit the dips of the superproject inside of its submodules.
+    pub inherit_superproject: bool,
+    /// The editor command for the notes.
+    pub editor: Option<String>,
+    pub clipboard: ClipboardBackend,
+    pub tui: TuiSettings,
+}
+
+impl UserSettings {
+    /// Load the config file, when there is one, with the environment
+    /// variables on top of it.
+    pub fn load() -> Result<Self, config::ConfigError> {
+        config::Config::builder()
+            .add_source(config::File::from(config_path()).required(false))
+            .add_source(
+                config::Environment::with_prefix(ENV_PREFIX)
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true),
+            )
+            .build()?
+            .try_deserialize()
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TuiSettings {
+    pub theme: ThemeName,
+    pub keys: KeyBindings,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// The keys of the actions on the TUI pages. The arrow keys, Enter, Esc
+/// and Ctrl-C always keep their meaning.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub up: char,
+    pub down: char,
+    pub command: char,
+    pub search: char,
+    pub help: char,
+    pub tags: char,
+    pub edit: char,
+    pub note: char,
+    pub delete: char,
+    pub run: char,
+    pub copy: char,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            up: 'k',
+            down: 'j',
+            command: ':',
+            search: '/',
+            help: '?',
+            tags: 't',
+            edit: 'e',
+            note: 'E',
+            delete: 'd',
+            run: 'r',
+            copy: 'y',
+        }
     }
 }
 
@@ -64,22 +193,35 @@ pub struct DatabaseSettings {
 }
 
 impl DatabaseSettings {
-    /// Build the database settings from all available configs
-    pub fn build(env: &Environment) -> Self {
+    /// Build the database settings from all available configs. The path from
+    /// the config is used unless `DEBUG_DB_PATH` is set in development.
+    pub fn build(env: &Environment, configured: Option<&str>) -> Self {
+        let configured = configured.map(|path| match path.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()
+                .expect("Failed to find home directory")
+                .join(rest)
+                .display()
+                .to_string(),
+            None => path.to_owned(),
+        });
         let path = match env {
             Environment::Development => {
                 if let Ok(path) = std::env::var("DEBUG_DB_PATH") {
                     path
+                } else if let Some(path) = configured {
+                    path
                 } else {
                     DB_NAME.to_string()
                 }
             }
-            Environment::Production => dirs::home_dir()
-                .expect("Failed to find home directory")
-                .join(".dips")
-                .join(DB_NAME)
-                .display()
-                .to_string(),
+            Environment::Production => configured.unwrap_or_else(|| {
+                dirs::home_dir()
+                    .expect("Failed to find home directory")
+                    .join(".dips")
+                    .join(DB_NAME)
+                    .display()
+                    .to_string()
+            }),
         };
 
         Self { path }
@@ -97,27 +239,44 @@ impl DatabaseSettings {
 pub struct Application {
     pub db_pool: SqlitePool,
     pub context_dir: RuntimeDirContext,
+    pub settings: Settings,
 }
 
 impl Application {
-    pub async fn build(config: Settings) -> Result<Self, ConfigError> {
-        let curr_path = std::env::current_dir().expect("Failed to read the current directory.");
+    pub async fn build(config: Settings) -> Result<Self, DipsError> {
+        let app = Self::connect(config).await?;
+        migrate_database(&app.db_pool).await?;
+        Ok(app)
+    }
+
+    /// Build the application without migrating the database. Only meant for
+    /// the commands that run on every prompt, like the shell hook, where the
+    /// migrations would cost more than the command itself.
+    pub async fn connect(config: Settings) -> Result<Self, DipsError> {
+        let path = match &config.dir {
+            // The scopes are stored with the absolute paths, like the
+            // current dir is.
+            Some(dir) => dir.canonicalize().map_err(|e| {
+                let message = format!("Failed to open {}: {e}", dir.display());
+                std::io::Error::new(e.kind(), message)
+            })?,
+            None => std::env::current_dir()?,
+        };
         let db_pool = get_database_connection(&config).await?;
-        migrate_database(&db_pool)
-            .await
-            .expect("Failed to initialize database");
-        let context_dir =
-            RuntimeDirContext::try_from(curr_path).expect("Failed to identify current context");
+        let context_dir = RuntimeDirContext::try_from(path)?
+            .with_dir_name_matching(config.user.match_dir_name)
+            .with_superproject_inheritance(config.user.inherit_superproject);
 
         Ok(Self {
             db_pool,
             context_dir,
+            settings: config,
         })
     }
 }
 
 /// Exclusivelly get the connetion to the database.
-async fn get_database_connection(config: &Settings) -> Result<SqlitePool, ConfigError> {
+async fn get_database_connection(config: &Settings) -> Result<SqlitePool, DipsError> {
     // TODO: Not sure if this is the right way to do this, but at the moment
     // we don't support custom setup, so we hardcode this value.
     // When we support custom setup for the database, the desting in memory
@@ -125,23 +284,22 @@ async fn get_database_connection(config: &Settings) -> Result<SqlitePool, Config
     if !config.database.path.contains(":memory:") {
         let database_path = Path::new(&config.database.path);
         if !database_path.exists() {
-            return Err(ConfigError::Uninitialized);
+            return Err(DipsError::Uninitialized);
         }
     }
 
-    let db_pool = SqlitePool::connect(&config.database.connection_string())
-        .await
-        .expect("Failed to connect to the database.");
+    let db_pool = SqlitePool::connect(&config.database.connection_string()).await?;
 
     Ok(db_pool)
 }
 
 /// Specific function to migrate the already established connection
-pub async fn migrate_database(conn: &SqlitePool) -> Result<(), sqlx::Error> {
+pub async fn migrate_database(conn: &SqlitePool) -> Result<(), DipsError> {
     sqlx::query("PRAGMA foreign_keys = ON;")
         .execute(conn)
         .await?;
     sqlx::migrate!("./migrations").run(conn).await?;
+    dir_context::backfill_canonical_remotes(conn).await?;
 
     Ok(())
 }
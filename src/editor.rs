@@ -0,0 +1,44 @@
+use std::io::{Error, Write};
+use std::path::Path;
+use std::process::Command;
+
+/// The editor from the environment, the same way git picks it. The one in
+/// the config wins over it, see `Settings::editor`.
+pub fn editor() -> String {
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|x| !x.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Open the editor on the file and wait until it's closed. The editor runs
+/// through the shell so it can carry its own arguments, e.g. `code --wait`.
+pub fn open(editor: &str, path: &Path) -> std::io::Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(path)
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::other(format!("The editor exited with {status}")))
+    }
+}
+
+/// Let the user edit the text in a temporary file. The name is only a hint
+/// for the editor's syntax highlighting. The file gets a random name and is
+/// created exclusively, so nobody can plant a link in its place.
+pub fn edit_text(editor: &str, name: &str, text: &str) -> std::io::Result<String> {
+    let mut file = tempfile::Builder::new()
+        .prefix("dips-")
+        .suffix(&format!("-{name}"))
+        .tempfile()?;
+    file.write_all(text.as_bytes())?;
+    file.flush()?;
+    open(editor, file.path())?;
+    // The editors often replace the file, so it's read again by the path.
+    std::fs::read_to_string(file.path())
+}
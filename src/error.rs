@@ -0,0 +1,106 @@
+use crate::commands::config::SetError;
+use crate::models::backup::BackupError;
+use crate::models::repo_file::RepoFileError;
+
+/// Everything that can make a command fail. The commands return it to
+/// `cli::run`, which prints the message and exits with the code of the
+/// variant, so the scripts can tell the failures apart:
+///
+/// | Code | Error                                        |
+/// |------|----------------------------------------------|
+/// | 1    | The command was cancelled by the user        |
+/// | 2    | Invalid arguments, reported by clap          |
+/// | 3    | Dips is not initialized                      |
+/// | 4    | Database error                               |
+/// | 5    | The database migrations failed               |
+/// | 6    | IO error, e.g. a file or the terminal        |
+/// | 7    | Git error                                    |
+/// | 8    | No scope matches                             |
+/// | 9    | No dip matches                               |
+/// | 10   | No tag matches                               |
+/// | 11   | The selector matches more than one dip       |
+/// | 12   | Invalid input, e.g. a duplicate or bad file  |
+///
+/// `dips run` is the exception once the command started: it exits with the
+/// code of the command, or 128 plus the signal that killed it like the
+/// shells do, so it can stand in for the command in the scripts. The codes
+/// above only mean a dips failure when nothing was run.
+#[derive(thiserror::Error, Debug)]
+pub enum DipsError {
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("Dips is not initialized. Please run `dips init`")]
+    Uninitialized,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Failed to migrate the database: {0}")]
+    Migration(#[from] sqlx::migrate::MigrateError),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("Git error: {0}")]
+    Git(String),
+    #[error("No scope found for {0}")]
+    ScopeNotFound(String),
+    #[error("No dip matches \"{0}\"")]
+    DipNotFound(String),
+    #[error("Tag {0} not found")]
+    TagNotFound(String),
+    #[error("Multiple dips match \"{0}\", use a longer id prefix to select one of them")]
+    AmbiguousSelector(String),
+    #[error("{0}")]
+    Validation(String),
+}
+
+impl DipsError {
+    /// The exit code of the process, see the table above.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Cancelled => 1,
+            Self::Uninitialized => 3,
+            Self::Database(_) => 4,
+            Self::Migration(_) => 5,
+            Self::Io(_) => 6,
+            Self::Git(_) => 7,
+            Self::ScopeNotFound(_) => 8,
+            Self::DipNotFound(_) => 9,
+            Self::TagNotFound(_) => 10,
+            Self::AmbiguousSelector(_) => 11,
+            Self::Validation(_) => 12,
+        }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation(message.into())
+    }
+}
+
+impl From<git2::Error> for DipsError {
+    fn from(value: git2::Error) -> Self {
+        Self::Git(value.message().to_owned())
+    }
+}
+
+/// The broken backups are the fault of the input, unlike the failed reads.
+impl From<BackupError> for DipsError {
+    fn from(value: BackupError) -> Self {
+        match value {
+            BackupError::Io(ref e) => Self::Io(std::io::Error::new(e.kind(), value.to_string())),
+            e => Self::Validation(e.to_string()),
+        }
+    }
+}
+
+impl From<SetError> for DipsError {
+    fn from(value: SetError) -> Self {
+        Self::Validation(value.to_string())
+    }
+}
+
+impl From<RepoFileError> for DipsError {
+    fn from(value: RepoFileError) -> Self {
+        match value {
+            RepoFileError::Io(ref e) => Self::Io(std::io::Error::new(e.kind(), value.to_string())),
+            e => Self::Validation(e.to_string()),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use git2::Repository;
+use git2::{BranchType, Repository};
 use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
@@ -6,30 +6,167 @@ pub struct GitRepository {
     pub path: PathBuf,
     pub dir_name: String,
     pub remote: Option<String>,
+    /// The checked out branch, none when the HEAD is detached.
+    pub branch: Option<String>,
+    /// A linked worktree, added by `git worktree add`.
+    pub is_worktree: bool,
+    /// The repository this one is a submodule of.
+    pub superproject: Option<Box<GitRepository>>,
 }
 
-pub fn git_repository(path: &Path) -> Option<GitRepository> {
-    match Repository::discover(path) {
-        Ok(repo) => {
-            // By default, it retunrs the ".git" dir and we are after the parent dir
-            let path = repo.path().parent().unwrap().to_path_buf();
-            let dir_name = path
-                .file_name()
-                .map(|x| x.to_string_lossy().to_string())
-                .unwrap();
-            let remote = repo
-                .find_remote("origin")
-                .ok()
-                .and_then(|x| x.url().map(String::from));
-            Ok(GitRepository {
-                path,
-                dir_name,
-                remote,
-            })
+impl GitRepository {
+    pub fn is_submodule(&self) -> bool {
+        self.superproject.is_some()
+    }
+
+    pub fn canonical_remote(&self) -> Option<String> {
+        self.remote.as_deref().and_then(canonical_remote)
+    }
+}
+
+/// The remote without the protocol, the user, the port and the `.git` suffix,
+/// e.g. `git@github.com:owner/repo.git` and `https://github.com/owner/repo`
+/// are both `github.com/owner/repo`. The local remotes have no host, so they
+/// have no canonical form.
+pub fn canonical_remote(url: &str) -> Option<String> {
+    let url = url.trim();
+    let (host, path) = match url.split_once("://") {
+        Some(("file", _)) => return None,
+        Some((_, rest)) => rest.split_once('/')?,
+        // The scp-like syntax, `user@host:owner/repo`.
+        None => url
+            .split_once(':')
+            .filter(|(host, _)| !host.contains('/'))?,
+    };
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    let host = host.split_once(':').map_or(host, |(host, _)| host);
+    let path = path.trim_matches('/');
+    let path = path
+        .strip_suffix(".git")
+        .unwrap_or(path)
+        .trim_end_matches('/');
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some(format!("{}/{path}", host.to_lowercase()))
+}
+
+/// The branch the HEAD points to. It's read from the symbolic reference, so
+/// it also works for a new branch without any commits yet.
+fn head_branch(repo: &Repository) -> Option<String> {
+    let head = repo.find_reference("HEAD").ok()?;
+    let target = head.symbolic_target()?;
+    target.strip_prefix("refs/heads/").map(String::from)
+}
+
+/// The names of the local branches of the repository the path is in.
+pub fn local_branches(path: &Path) -> Result<Vec<String>, git2::Error> {
+    let repo = Repository::discover(path)?;
+    let mut names = vec![];
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            names.push(name.to_owned());
+        }
+    }
+    Ok(names)
+}
+
+/// The git dir shared by the worktrees, what `git rev-parse --git-common-dir`
+/// prints. The linked worktrees point to it from the `commondir` file of
+/// their own git dir, which libgit2 reads too but git2 doesn't expose.
+fn commondir(repo: &Repository) -> PathBuf {
+    let path = repo.path();
+    match std::fs::read_to_string(path.join("commondir")) {
+        Ok(content) => {
+            let dir = path.join(content.trim());
+            dir.canonicalize().unwrap_or(dir)
         }
-        Err(e) => Err(e),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// The main repository of a linked worktree, which knows all of them. It
+/// can be bare or have its git dir apart from the working directory.
+fn main_repository(repo: Repository) -> Result<Repository, git2::Error> {
+    if repo.is_worktree() {
+        Repository::open(commondir(&repo))
+    } else {
+        Ok(repo)
     }
-    .ok()
+}
+
+/// The working directories of the repository the path is in and of its
+/// linked worktrees, which all share the same branches.
+pub fn worktree_roots(path: &Path) -> Result<Vec<PathBuf>, git2::Error> {
+    let repo = main_repository(Repository::discover(path)?)?;
+    let mut roots = repo
+        .workdir()
+        .map(Path::to_path_buf)
+        .into_iter()
+        .collect::<Vec<_>>();
+    for name in repo.worktrees()?.iter().flatten() {
+        roots.push(repo.find_worktree(name)?.path().to_path_buf());
+    }
+    // Without the trailing slash and the symlinks, the same as the paths of
+    // the scopes.
+    Ok(roots
+        .into_iter()
+        .map(|x| x.canonicalize().unwrap_or(x).components().collect())
+        .collect())
+}
+
+/// The directory name of the repository. The linked worktrees are named
+/// after their main repository, the bare one after its git dir without the
+/// `.git` suffix.
+fn dir_name(repo: &Repository, workdir: &Path) -> Option<String> {
+    if !repo.is_worktree() {
+        return workdir.file_name().map(|x| x.to_string_lossy().to_string());
+    }
+    let common = commondir(repo);
+    let main = Repository::open(&common).ok()?;
+    match main.workdir() {
+        Some(main) => main.components().next_back(),
+        None => common.components().next_back(),
+    }
+    .map(|x| x.as_os_str().to_string_lossy())
+    .map(|x| x.strip_suffix(".git").unwrap_or(&x).to_string())
+}
+
+/// The repository of the parent directory, when the one in the workdir is
+/// its submodule. A repository nested without being a submodule stays on
+/// its own.
+fn superproject(workdir: &Path) -> Option<GitRepository> {
+    let outer = git_repository(workdir.parent()?)?;
+    let repo = Repository::open(&outer.path).ok()?;
+    let is_submodule = repo
+        .submodules()
+        .ok()?
+        .iter()
+        .any(|x| outer.path.join(x.path()) == workdir);
+    is_submodule.then_some(outer)
+}
+
+/// Find the repository the path is in. The root is its working directory,
+/// which is also right for the linked worktrees and the submodules, whose
+/// `.git` dirs live somewhere else. The bare repositories have no files to
+/// scope the dips to.
+pub fn git_repository(path: &Path) -> Option<GitRepository> {
+    let repo = Repository::discover(path).ok()?;
+    // Without the trailing slash, the same as the paths of the scopes.
+    let workdir = repo.workdir()?.components().collect::<PathBuf>();
+    let remote = repo
+        .find_remote("origin")
+        .ok()
+        .and_then(|x| x.url().map(String::from));
+    Some(GitRepository {
+        dir_name: dir_name(&repo, &workdir)?,
+        remote,
+        branch: head_branch(&repo),
+        is_worktree: repo.is_worktree(),
+        superproject: superproject(&workdir).map(Box::new),
+        path: workdir,
+    })
 }
 
 #[cfg(test)]
@@ -61,6 +198,25 @@ mod test {
         (temp_dir, repo_path.to_path_buf())
     }
 
+    #[test]
+    fn canonical_remote_is_the_same_for_every_protocol() {
+        for url in [
+            "git@github.com:owner/repo.git",
+            "ssh://git@github.com:22/owner/repo.git",
+            "https://github.com/owner/repo",
+            "https://user@GitHub.com/owner/repo.git/",
+            "git://github.com/owner/repo.git",
+        ] {
+            assert_eq!(
+                canonical_remote(url).as_deref(),
+                Some("github.com/owner/repo"),
+                "{url}"
+            );
+        }
+        assert_eq!(canonical_remote("/srv/git/repo.git"), None);
+        assert_eq!(canonical_remote("file:///srv/git/repo.git"), None);
+    }
+
     #[test]
     fn find_git_repository() {
         let repo_name = Word().fake();
@@ -75,8 +231,118 @@ mod test {
         assert_eq!(repo.remote, Some(repo_remote));
         assert_eq!(
             repo.path,
-            // Had to add in the "private" as the tempfile adds it. It's not part of the test.
-            std::path::Path::new("/private").join(path.strip_prefix("/").unwrap())
+            // Canonicalize as the tempfile might live behind a symlink (e.g. "/private" on macOS).
+            path.canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn branch_is_read_before_the_first_commit() {
+        let (_dir, path) = temp_repo("repo", &remote());
+        let repo = git2::Repository::open(&path).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+
+        let repo = git_repository(&path).unwrap();
+
+        assert_eq!(repo.branch.as_deref(), Some("feature"));
+    }
+
+    fn commit(repo: &git2::Repository) -> git2::Oid {
+        let signature = git2::Signature::now("dips", "dips@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .expect("Failed to commit.")
+    }
+
+    #[test]
+    fn worktree_root_is_its_workdir() {
+        let (dir, path) = temp_repo("repo", &remote());
+        commit(&git2::Repository::open(&path).unwrap());
+        let worktree_path = dir.path().join("feature");
+        git2::Repository::open(&path)
+            .unwrap()
+            .worktree("feature", &worktree_path, None)
+            .expect("Failed to add the worktree.");
+
+        let repo = git_repository(&worktree_path.join("")).unwrap();
+
+        assert!(repo.is_worktree);
+        assert!(!repo.is_submodule());
+        assert_eq!(repo.path, worktree_path.canonicalize().unwrap());
+        assert_eq!(repo.dir_name, "repo");
+        assert_eq!(repo.branch.as_deref(), Some("feature"));
+    }
+
+    #[test]
+    fn worktree_is_named_after_a_separate_git_dir_repository() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let workdir = dir.path().join("repo");
+        let mut options = git2::RepositoryInitOptions::new();
+        options.workdir_path(&workdir);
+        let repo = git2::Repository::init_opts(dir.path().join("git/repo.git"), &options).unwrap();
+        commit(&repo);
+        let worktree_path = dir.path().join("feature");
+        repo.worktree("feature", &worktree_path, None).unwrap();
+
+        let worktree = git_repository(&worktree_path).unwrap();
+        let roots = worktree_roots(&worktree_path).unwrap();
+
+        assert_eq!(worktree.dir_name, "repo");
+        assert_eq!(
+            roots,
+            vec![
+                workdir.canonicalize().unwrap(),
+                worktree_path.canonicalize().unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn worktree_of_a_bare_repository_is_named_after_it() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = git2::Repository::init_bare(dir.path().join("api.git")).unwrap();
+        let signature = git2::Signature::now("dips", "dips@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        let worktree_path = dir.path().join("main");
+        repo.worktree("main", &worktree_path, None).unwrap();
+
+        let worktree = git_repository(&worktree_path).unwrap();
+        let roots = worktree_roots(&worktree_path).unwrap();
+
+        assert_eq!(worktree.dir_name, "api");
+        assert_eq!(roots, vec![worktree_path.canonicalize().unwrap()]);
+    }
+
+    #[test]
+    fn submodule_knows_its_superproject() {
+        let (_dir, path) = temp_repo("repo", &remote());
+        let repo = git2::Repository::open(&path).unwrap();
+        repo.submodule(
+            "https://github.com/owner/lib.git",
+            Path::new("libs/lib"),
+            true,
+        )
+        .expect("Failed to add the submodule.");
+        git2::Repository::init(path.join("vendor")).unwrap();
+
+        let submodule = git_repository(&path.join("libs/lib")).unwrap();
+        let nested = git_repository(&path.join("vendor")).unwrap();
+
+        assert!(!submodule.is_worktree);
+        assert_eq!(
+            submodule.path,
+            path.join("libs/lib").canonicalize().unwrap()
+        );
+        assert_eq!(
+            submodule.superproject.map(|x| x.path),
+            Some(path.canonicalize().unwrap())
         );
+        assert!(!nested.is_submodule());
     }
 }
@@ -1,6 +1,9 @@
 pub mod cli;
+pub mod clipboard;
 pub mod commands;
 pub mod configuration;
+pub mod editor;
+pub mod error;
 pub mod git;
 pub mod models;
 pub mod tui;
@@ -2,5 +2,8 @@ use dips::cli;
 
 #[tokio::main]
 async fn main() {
-    cli::run().await;
+    if let Err(e) = cli::run().await {
+        eprintln!("ERROR: {e}");
+        std::process::exit(e.exit_code());
+    }
 }
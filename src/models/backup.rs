@@ -0,0 +1,408 @@
+use crate::git;
+use crate::models::dip;
+use crate::models::dir_context::{self, ContextScope, DirContext};
+use crate::models::tag::Tag;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Bumped whenever the shape of the backup changes in an incompatible way.
+pub static BACKUP_VERSION: u32 = 1;
+
+#[derive(thiserror::Error, Debug)]
+pub enum BackupError {
+    #[error("Failed to access the backup file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse the backup file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Failed to parse the backup file: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("The markdown export is meant for reading and can't be imported")]
+    Markdown,
+    #[error("Unsupported backup version {0}")]
+    Version(u32),
+}
+
+/// Which dips go into the backup.
+#[derive(Debug, Clone)]
+pub enum BackupSelection {
+    All,
+    Scope(ContextScope),
+}
+
+impl BackupSelection {
+    /// The values bound to the `$1 or dir_context_id IS $2` filter.
+    fn bindings(&self) -> (bool, Option<String>) {
+        match self {
+            Self::All => (true, None),
+            Self::Scope(scope) => (false, scope.id().map(|x| x.to_string())),
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct BackupDip {
+    #[sqlx(try_from = "uuid::fmt::Hyphenated")]
+    pub id: Uuid,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub dir_context_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct BackupDipTag {
+    pub dip_id: String,
+    pub tag_id: String,
+}
+
+/// A copy of the database tables. The rows keep their ids, so importing the
+/// same backup twice doesn't duplicate anything.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct Backup {
+    pub version: u32,
+    /// Made of every scope, so replacing with it removes all the existing
+    /// data. The backup of a scope only replaces the scopes it has.
+    #[serde(default)]
+    pub all: bool,
+    #[serde(default)]
+    pub dir_contexts: Vec<DirContext>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub dips: Vec<BackupDip>,
+    #[serde(default)]
+    pub dips_tags: Vec<BackupDipTag>,
+}
+
+impl Default for Backup {
+    fn default() -> Self {
+        Self {
+            version: BACKUP_VERSION,
+            all: false,
+            dir_contexts: vec![],
+            tags: vec![],
+            dips: vec![],
+            dips_tags: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportMode {
+    /// Keep the existing data and add what is missing.
+    Merge,
+    /// Remove the existing data of the backed up scopes first.
+    Replace,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub dips: usize,
+    pub skipped: usize,
+    pub dir_contexts: usize,
+    pub tags: usize,
+}
+
+pub async fn get_backup(
+    conn: &SqlitePool,
+    selection: &BackupSelection,
+) -> Result<Backup, sqlx::Error> {
+    let (all, scope_id) = selection.bindings();
+    let dir_contexts = sqlx::query_as(
+        r"
+        select * from dir_contexts
+        where $1 or id = $2
+        order by dir_path
+        ",
+    )
+    .bind(all)
+    .bind(&scope_id)
+    .fetch_all(conn)
+    .await?;
+    let dips = sqlx::query_as(
+        r"
+        select id, value, note, dir_context_id, branch, archived_at, created_at, updated_at
+        from dips
+        where $1 or dir_context_id IS $2
+        order by created_at, id
+        ",
+    )
+    .bind(all)
+    .bind(&scope_id)
+    .fetch_all(conn)
+    .await?;
+    let dips_tags = sqlx::query_as(
+        r"
+        select dips_tags.dip_id, dips_tags.tag_id from dips_tags
+        join dips on dips.id = dips_tags.dip_id
+        where $1 or dips.dir_context_id IS $2
+        order by dips_tags.dip_id, dips_tags.tag_id
+        ",
+    )
+    .bind(all)
+    .bind(&scope_id)
+    .fetch_all(conn)
+    .await?;
+    // The unused tags only make sense in the full backup.
+    let tags = sqlx::query_as(
+        r"
+        select * from tags
+        where $1 or id in (
+            select dips_tags.tag_id from dips_tags
+            join dips on dips.id = dips_tags.dip_id
+            where dips.dir_context_id IS $2
+        )
+        order by name
+        ",
+    )
+    .bind(all)
+    .bind(&scope_id)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(Backup {
+        version: BACKUP_VERSION,
+        all,
+        dir_contexts,
+        tags,
+        dips,
+        dips_tags,
+    })
+}
+
+/// Read the backup file, the format is picked by the file extension.
+pub fn read(path: &Path) -> Result<Backup, BackupError> {
+    let content = std::fs::read_to_string(path)?;
+    let backup: Backup = match path.extension().and_then(|x| x.to_str()) {
+        Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+        Some("md") => return Err(BackupError::Markdown),
+        _ => serde_json::from_str(&content)?,
+    };
+    if backup.version > BACKUP_VERSION {
+        return Err(BackupError::Version(backup.version));
+    }
+    Ok(backup)
+}
+
+async fn delete_all(tx: &mut Transaction<'_, Sqlite>) -> Result<(), sqlx::Error> {
+    for table in ["dips_tags", "dips", "tags", "dir_contexts"] {
+        sqlx::query(&format!("delete from {table}"))
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Remove the dips of the scopes the backup is mapped to, and the global
+/// ones when it has any, so they are replaced by the ones of the backup.
+async fn delete_scoped(
+    tx: &mut Transaction<'_, Sqlite>,
+    backup: &Backup,
+    scope_ids: &HashMap<String, String>,
+) -> Result<(), sqlx::Error> {
+    let targets = scope_ids.values().collect::<HashSet<_>>();
+    for id in targets.into_iter().filter_map(|x| Uuid::parse_str(x).ok()) {
+        dir_context::delete_dips(tx, &id).await?;
+    }
+    if backup.dips.iter().any(|x| x.dir_context_id.is_none()) {
+        sqlx::query(
+            r"
+            delete from dips_tags
+            where dip_id in (select id from dips where dir_context_id is null)
+            ",
+        )
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query("delete from dips where dir_context_id is null")
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Map the scopes of the backup to the existing ones, by the id, then by
+/// the same remote and path inside of the repository like `db_find_one`, so
+/// the other clones share them, then by the same directory. The rest are
+/// created.
+async fn restore_dir_contexts(
+    tx: &mut Transaction<'_, Sqlite>,
+    backup: &Backup,
+    summary: &mut ImportSummary,
+) -> Result<HashMap<String, String>, sqlx::Error> {
+    let mut ids = HashMap::new();
+    for dir in &backup.dir_contexts {
+        let id = dir.id.to_string();
+        let canonical_remote = dir.git_remote.as_deref().and_then(git::canonical_remote);
+        let existing: Option<String> = sqlx::query_scalar(
+            r"
+            select id from dir_contexts
+            where id = $1
+            or (canonical_remote = $3 and subpath = $4)
+            or dir_path = $2
+            order by
+                case
+                    when id = $1 then 0
+                    when canonical_remote = $3 and subpath = $4 then 1
+                    else 2
+                end
+            limit 1
+            ",
+        )
+        .bind(&id)
+        .bind(&dir.dir_path)
+        .bind(&canonical_remote)
+        .bind(&dir.subpath)
+        .fetch_optional(&mut **tx)
+        .await?;
+        let target = match existing {
+            Some(existing) => existing,
+            None => {
+                sqlx::query(
+                    r"
+                    insert into dir_contexts(
+                        id, dir_path, subpath, git_remote, canonical_remote, git_dir_name,
+                        created_at, updated_at
+                    ) values ($1, $2, $3, $4, $5, $6, $7, $8)
+                    ",
+                )
+                .bind(&id)
+                .bind(&dir.dir_path)
+                .bind(&dir.subpath)
+                .bind(&dir.git_remote)
+                .bind(&canonical_remote)
+                .bind(&dir.git_dir_name)
+                .bind(dir.created_at)
+                .bind(dir.updated_at)
+                .execute(&mut **tx)
+                .await?;
+                summary.dir_contexts += 1;
+                id.clone()
+            }
+        };
+        ids.insert(id, target);
+    }
+    Ok(ids)
+}
+
+/// Tag names are unique, so the tags of the backup are matched by the name.
+async fn restore_tags(
+    tx: &mut Transaction<'_, Sqlite>,
+    backup: &Backup,
+    summary: &mut ImportSummary,
+) -> Result<HashMap<String, String>, sqlx::Error> {
+    let mut ids = HashMap::new();
+    for tag in &backup.tags {
+        let existing: Option<String> = sqlx::query_scalar("select id from tags where name = $1")
+            .bind(&tag.name)
+            .fetch_optional(&mut **tx)
+            .await?;
+        let target = match existing {
+            Some(existing) => existing,
+            None => {
+                sqlx::query("insert into tags (id, name, created_at) values ($1, $2, $3)")
+                    .bind(&tag.id)
+                    .bind(&tag.name)
+                    .bind(tag.created_at)
+                    .execute(&mut **tx)
+                    .await?;
+                summary.tags += 1;
+                tag.id.clone()
+            }
+        };
+        ids.insert(tag.id.clone(), target);
+    }
+    Ok(ids)
+}
+
+/// Write the backup into the database. The dips that already exist, either
+/// with the same id or the same value in a matching scope, are skipped.
+pub async fn restore(
+    tx: &mut Transaction<'_, Sqlite>,
+    backup: &Backup,
+    mode: ImportMode,
+) -> Result<ImportSummary, sqlx::Error> {
+    let mut summary = ImportSummary::default();
+    let replace = mode == ImportMode::Replace;
+    if replace && backup.all {
+        delete_all(tx).await?;
+    }
+
+    let scope_ids = restore_dir_contexts(tx, backup, &mut summary).await?;
+    if replace && !backup.all {
+        delete_scoped(tx, backup, &scope_ids).await?;
+    }
+    let tag_ids = restore_tags(tx, backup, &mut summary).await?;
+
+    let mut imported = HashSet::new();
+    for item in &backup.dips {
+        let id = item.id.to_string();
+        let dir_context_id = match &item.dir_context_id {
+            Some(scope_id) => match scope_ids.get(scope_id.as_str()) {
+                Some(target) => Some(target),
+                // The scope is not in the backup, so we don't know where it belongs.
+                None => {
+                    summary.skipped += 1;
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let target_scope = dir_context_id.and_then(|x| Uuid::parse_str(x).ok());
+        let taken: Option<String> = sqlx::query_scalar("select id from dips where id = $1")
+            .bind(&id)
+            .fetch_optional(&mut **tx)
+            .await?;
+        let exists = taken.is_some()
+            || dip::value_exists(&mut **tx, &item.value, target_scope.as_ref()).await?;
+        if exists {
+            summary.skipped += 1;
+            continue;
+        }
+
+        sqlx::query(
+            r"
+            insert into dips(
+                id, value, note, created_at, updated_at, dir_context_id, branch, archived_at
+            )
+            values($1, $2, $3, $4, $5, $6, $7, $8)
+            ",
+        )
+        .bind(&id)
+        .bind(&item.value)
+        .bind(&item.note)
+        .bind(item.created_at)
+        .bind(item.updated_at)
+        .bind(dir_context_id)
+        .bind(&item.branch)
+        .bind(item.archived_at)
+        .execute(&mut **tx)
+        .await?;
+        imported.insert(id);
+        summary.dips += 1;
+    }
+
+    for link in &backup.dips_tags {
+        let Some(tag_id) = tag_ids.get(&link.tag_id) else {
+            continue;
+        };
+        if !imported.contains(&link.dip_id) {
+            continue;
+        }
+        sqlx::query("insert or ignore into dips_tags (dip_id, tag_id) values ($1, $2)")
+            .bind(&link.dip_id)
+            .bind(tag_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    Ok(summary)
+}
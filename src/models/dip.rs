@@ -1,19 +1,40 @@
+use crate::models::dir_context::{ScopeLink, ScopeOrigin};
 use crate::models::tag;
+use crate::models::tag_expr::TagExpr;
 use sqlx::types::Uuid;
 use sqlx::{Sqlite, SqlitePool, Transaction};
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug)]
+/// Markers wrapping the matched terms in the search snippets.
+pub static HIGHLIGHT_START: &str = "\u{2}";
+pub static HIGHLIGHT_END: &str = "\u{3}";
+
+#[derive(Debug, Clone)]
 pub struct DipsFilter {
     scope_id: Option<Uuid>,
+    any_scope: bool,
     search: Option<String>,
+    tags: Option<TagExpr>,
+    /// When set, the dips of the other branches are hidden, `Some(None)`
+    /// keeps only the dips without a branch.
+    branch: Option<Option<String>>,
+}
+
+impl Default for DipsFilter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DipsFilter {
     pub fn new() -> Self {
         Self {
             scope_id: None,
+            any_scope: false,
             search: None,
+            tags: None,
+            branch: None,
         }
     }
 
@@ -24,12 +45,65 @@ impl DipsFilter {
         }
     }
 
+    /// Ignore the scope and match the dips from all the scopes.
+    pub fn with_any_scope(self) -> Self {
+        Self {
+            any_scope: true,
+            ..self
+        }
+    }
+
     pub fn with_search(self, value: &str) -> Self {
         Self {
             search: Some(value.to_owned()),
             ..self
         }
     }
+
+    /// Only keep the dips whose tags match the expression.
+    pub fn with_tags(self, expr: Option<TagExpr>) -> Self {
+        Self { tags: expr, ..self }
+    }
+
+    /// Only keep the dips of the checked out branch and the ones without
+    /// a branch.
+    pub fn with_branch(self, branch: Option<&str>) -> Self {
+        Self {
+            branch: Some(branch.map(String::from)),
+            ..self
+        }
+    }
+
+    pub fn tags(&self) -> Option<&TagExpr> {
+        self.tags.as_ref()
+    }
+
+    /// The tag condition for the query and the json array of the tag names
+    /// to bind to `param`.
+    fn tags_sql(&self, param: &str) -> (String, String) {
+        let mut names = vec![];
+        let sql = match &self.tags {
+            Some(expr) => expr.to_sql(param, &mut names),
+            None => "1".to_string(),
+        };
+        let names = serde_json::to_string(&names).expect("Failed to serialize the tag names");
+        (sql, names)
+    }
+}
+
+/// Turn the user input into a FTS5 query. Every word is quoted so the FTS
+/// syntax characters are taken literally and matched as a prefix so the
+/// results show up while the user is still typing the word.
+fn fts_query(search: &str) -> Option<String> {
+    let terms = search
+        .split_whitespace()
+        .map(|x| format!("\"{}\"*", x.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
 }
 
 #[derive(serde::Serialize, Debug)]
@@ -58,19 +132,24 @@ impl Dip {
     }
 }
 
-#[derive(Debug)]
+#[derive(serde::Serialize, Debug)]
 pub struct DipTags(Vec<tag::TagMeta>);
 
-impl ToString for DipTags {
-    fn to_string(&self) -> String {
-        self.iter()
-            .map(|x| x.name.as_str())
-            .collect::<Vec<_>>()
-            .join(" ")
+impl DipTags {
+    pub fn matches(&self, expr: &TagExpr) -> bool {
+        let names = self.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+        expr.matches(&names)
     }
 }
 
-#[derive(Debug, sqlx::FromRow)]
+impl std::fmt::Display for DipTags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = self.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+        write!(f, "{}", names.join(" "))
+    }
+}
+
+#[derive(serde::Serialize, Debug, sqlx::FromRow)]
 pub struct DipRowFull {
     #[sqlx(try_from = "uuid::fmt::Hyphenated")]
     pub id: Uuid,
@@ -81,15 +160,25 @@ pub struct DipRowFull {
     pub updated_at: chrono::NaiveDateTime,
     pub git_remote: Option<String>,
     pub git_dir_name: Option<String>,
-    pub dir_path: String,
+    pub dir_path: Option<String>,
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
     #[sqlx(try_from = "String")]
     pub tags: DipTags,
+    /// Part of the dip that matched the search with the terms wrapped
+    /// in the highlight markers. Only present for the search results.
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// The scope in the inherited chain this dip comes from.
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<ScopeOrigin>,
 }
 
-impl TryFrom<String> for DipTags {
-    type Error = std::convert::Infallible;
-
-    fn try_from(s: String) -> Result<Self, Self::Error> {
+impl From<String> for DipTags {
+    fn from(s: String) -> Self {
         let tags = s
             .split(',')
             .filter_map(|tag| {
@@ -104,7 +193,13 @@ impl TryFrom<String> for DipTags {
                 }
             })
             .collect();
-        Ok(DipTags(tags))
+        DipTags(tags)
+    }
+}
+
+impl From<Vec<tag::TagMeta>> for DipTags {
+    fn from(tags: Vec<tag::TagMeta>) -> Self {
+        DipTags(tags)
     }
 }
 
@@ -120,8 +215,12 @@ pub async fn get_filtered(
     conn: &SqlitePool,
     filter: DipsFilter,
 ) -> Result<Vec<DipRowFull>, sqlx::Error> {
-    let search = format!("%{}%", filter.search.unwrap_or_default());
-    sqlx::query_as(
+    if let Some(search) = filter.search.as_deref().and_then(fts_query) {
+        return get_searched(conn, &filter, &search).await;
+    }
+
+    let (tags_sql, tag_names) = filter.tags_sql("$3");
+    sqlx::query_as(&format!(
         r"
        select dips.*, 
             dir_contexts.dir_path, 
@@ -132,17 +231,89 @@ pub async fn get_filtered(
         left join dir_contexts on dips.dir_context_id = dir_contexts.id
         LEFT JOIN dips_tags ON dips.id = dips_tags.dip_id
         LEFT JOIN tags ON dips_tags.tag_id = tags.id
-        WHERE dips.dir_context_id = $1
-        and LOWER(dips.value) LIKE LOWER($2)
+        WHERE ($2 or dips.dir_context_id IS $1)
+          AND ({tags_sql})
+          AND dips.archived_at IS NULL
+          AND ($4 or dips.branch IS NULL or dips.branch = $5)
         GROUP BY dips.id
         ",
-    )
+    ))
+    .bind(filter.scope_id.map(|x| x.to_string()))
+    .bind(filter.any_scope)
+    .bind(tag_names)
+    .bind(filter.branch.is_none())
+    .bind(filter.branch.flatten())
+    .fetch_all(conn)
+    .await
+}
+
+/// Full text search over the value, note and tag names ranked by BM25
+/// where the value weights the most and the note the least.
+async fn get_searched(
+    conn: &SqlitePool,
+    filter: &DipsFilter,
+    search: &str,
+) -> Result<Vec<DipRowFull>, sqlx::Error> {
+    let (tags_sql, tag_names) = filter.tags_sql("$6");
+    sqlx::query_as(&format!(
+        r"
+        with matches as materialized (
+            select dip_id,
+                bm25(dips_fts, 0.0, 10.0, 1.0, 5.0) as rank,
+                snippet(dips_fts, -1, $4, $5, '…', 12) as snippet
+            from dips_fts
+            where dips_fts match $3
+        )
+        select dips.*,
+            dir_contexts.dir_path,
+            dir_contexts.git_remote,
+            dir_contexts.git_dir_name,
+            GROUP_CONCAT(tags.id || ':' || tags.name) as tags,
+            matches.snippet
+        from matches
+        join dips on dips.id = matches.dip_id
+        left join dir_contexts on dips.dir_context_id = dir_contexts.id
+        LEFT JOIN dips_tags ON dips.id = dips_tags.dip_id
+        LEFT JOIN tags ON dips_tags.tag_id = tags.id
+        WHERE ($2 or dips.dir_context_id IS $1)
+          AND ({tags_sql})
+          AND dips.archived_at IS NULL
+          AND ($7 or dips.branch IS NULL or dips.branch = $8)
+        GROUP BY dips.id
+        ORDER BY matches.rank
+        ",
+    ))
     .bind(filter.scope_id.map(|x| x.to_string()))
+    .bind(filter.any_scope)
     .bind(search)
+    .bind(HIGHLIGHT_START)
+    .bind(HIGHLIGHT_END)
+    .bind(tag_names)
+    .bind(filter.branch.is_none())
+    .bind(filter.branch.clone().flatten())
     .fetch_all(conn)
     .await
 }
 
+/// Query the dips for every scope in the chain. The results keep the order
+/// of the chain, so they come grouped by their origin.
+pub async fn get_inherited(
+    conn: &SqlitePool,
+    chain: &[ScopeLink],
+    filter: DipsFilter,
+) -> Result<Vec<DipRowFull>, sqlx::Error> {
+    let mut items = vec![];
+    for link in chain {
+        let filter = filter.clone().with_scope_id(link.scope.id());
+        let rows = get_filtered(conn, filter).await?;
+        items.extend(rows.into_iter().map(|x| DipRowFull {
+            origin: Some(link.origin),
+            ..x
+        }));
+    }
+    Ok(items)
+}
+
 pub async fn get_all(conn: &SqlitePool) -> Result<Vec<DipRowFull>, sqlx::Error> {
     sqlx::query_as(
         r#"
@@ -223,3 +394,142 @@ pub async fn delete(conn: &SqlitePool, id: &Uuid) -> Result<(), sqlx::Error> {
         .await?;
     Ok(())
 }
+
+pub async fn get_by_id_prefix(
+    conn: &SqlitePool,
+    prefix: &str,
+) -> Result<Vec<DipRowFull>, sqlx::Error> {
+    let prefix = format!("{}%", prefix.to_lowercase());
+    sqlx::query_as(
+        r"
+       select dips.*, 
+            dir_contexts.dir_path, 
+            dir_contexts.git_remote, 
+            dir_contexts.git_dir_name,
+            GROUP_CONCAT(tags.id || ':' || tags.name) as tags
+        from dips
+        left join dir_contexts on dips.dir_context_id = dir_contexts.id
+        LEFT JOIN dips_tags ON dips.id = dips_tags.dip_id
+        LEFT JOIN tags ON dips_tags.tag_id = tags.id
+        WHERE dips.id LIKE $1
+        GROUP BY dips.id
+        ",
+    )
+    .bind(prefix)
+    .fetch_all(conn)
+    .await
+}
+
+/// Change the value and the note, the ones that are `None` are kept. The
+/// note is removed with `Some(None)`.
+pub async fn update(
+    conn: &SqlitePool,
+    id: &Uuid,
+    value: Option<&str>,
+    note: Option<Option<&str>>,
+) -> Result<(), sqlx::Error> {
+    let id = id.to_string();
+    let now = chrono::Utc::now().naive_utc();
+    sqlx::query(
+        r"
+        update dips
+        set value = coalesce($2, value),
+            note = case when $5 then $3 else note end,
+            updated_at = $4
+        where id = $1
+        ",
+    )
+    .bind(id)
+    .bind(value)
+    .bind(note.flatten())
+    .bind(now)
+    .bind(note.is_some())
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Check if the value is already stored in the scope, `None` being the
+/// global one.
+pub async fn value_exists<'c, E>(
+    conn: E,
+    value: &str,
+    scope_id: Option<&Uuid>,
+) -> Result<bool, sqlx::Error>
+where
+    E: sqlx::Executor<'c, Database = Sqlite>,
+{
+    sqlx::query_scalar::<_, String>(
+        "select id from dips where value = $1 and dir_context_id is $2 and archived_at is null limit 1",
+    )
+    .bind(value)
+    .bind(scope_id.map(|x| x.to_string()))
+    .fetch_optional(conn)
+    .await
+    .map(|x| x.is_some())
+}
+
+/// Attach the dip to the branch, it only shows while the branch is checked out.
+pub async fn set_branch(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: &Uuid,
+    branch: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("update dips set branch = $2 where id = $1")
+        .bind(id.to_string())
+        .bind(branch)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// A dip attached to a branch, with the scope it's in.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct BranchDip {
+    #[sqlx(try_from = "uuid::fmt::Hyphenated")]
+    pub id: Uuid,
+    pub value: String,
+    pub branch: String,
+    pub dir_path: String,
+}
+
+/// The dips attached to a branch in the scopes inside of the directories,
+/// the worktrees of a git repository. The other clones with the same remote
+/// have their own branches, so their scopes are left out, as are the
+/// archived dips.
+pub async fn get_branch_dips(
+    conn: &SqlitePool,
+    roots: &[PathBuf],
+) -> Result<Vec<BranchDip>, sqlx::Error> {
+    let items: Vec<BranchDip> = sqlx::query_as(
+        r"
+        select dips.id, dips.value, dips.branch, dir_contexts.dir_path
+        from dips
+        join dir_contexts on dips.dir_context_id = dir_contexts.id
+        where dips.branch is not null
+          and dips.archived_at is null
+        order by dips.branch, dips.value
+        ",
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(items
+        .into_iter()
+        .filter(|x| {
+            roots
+                .iter()
+                .any(|root| Path::new(&x.dir_path).starts_with(root))
+        })
+        .collect())
+}
+
+/// Hide the dip everywhere, while keeping it in the database.
+pub async fn archive(tx: &mut Transaction<'_, Sqlite>, id: &Uuid) -> Result<(), sqlx::Error> {
+    let now = chrono::Utc::now().naive_utc();
+    sqlx::query("update dips set archived_at = $2 where id = $1")
+        .bind(id.to_string())
+        .bind(now)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use crate::error::DipsError;
 use crate::git;
 use sqlx::types::Uuid;
 use sqlx::{Sqlite, SqlitePool, Transaction};
@@ -9,6 +10,12 @@ pub struct ScopesFilter {
     search: Option<String>,
 }
 
+impl Default for ScopesFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ScopesFilter {
     pub fn new() -> Self {
         Self { search: None }
@@ -17,7 +24,6 @@ impl ScopesFilter {
     pub fn with_search(self, value: &str) -> Self {
         Self {
             search: Some(value.to_owned()),
-            ..self
         }
     }
 }
@@ -59,24 +65,38 @@ impl From<Option<DirContext>> for ContextScope {
     }
 }
 
-#[derive(serde::Deserialize, sqlx::FromRow, Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, Debug, Clone)]
 pub struct DirContext {
     #[sqlx(try_from = "uuid::fmt::Hyphenated")]
     pub id: Uuid,
     pub git_remote: Option<String>,
+    /// The remote in the `host/owner/repo` form, the same for its SSH and
+    /// HTTPS URLs.
+    #[serde(default)]
+    pub canonical_remote: Option<String>,
     pub git_dir_name: Option<String>,
     pub dir_path: String,
+    /// The path inside of the git repository, empty at its root.
+    #[serde(default)]
+    pub subpath: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
 }
 
 impl DirContext {
-    fn new(dir_path: &str, git_dir_name: Option<String>, git_remote: Option<String>) -> Self {
+    fn new(
+        dir_path: &str,
+        subpath: &str,
+        git_dir_name: Option<String>,
+        git_remote: Option<String>,
+    ) -> Self {
         let now: chrono::NaiveDateTime = chrono::Utc::now().date_naive().into();
         let id = Uuid::new_v4();
         Self {
             id,
             dir_path: dir_path.into(),
+            subpath: subpath.into(),
+            canonical_remote: git_remote.as_deref().and_then(git::canonical_remote),
             git_remote,
             git_dir_name,
             created_at: now,
@@ -102,17 +122,21 @@ pub async fn get_filtered(
     .await
 }
 
+/// Find the scope of the directory the app runs in, or create it when it's
+/// the first dip there.
 pub async fn get_or_create_current(
     tx: &mut Transaction<'_, Sqlite>,
-) -> Result<DirContext, anyhow::Error> {
-    let current_path = std::env::current_dir()?;
-    let curr_path_string = current_path.display().to_string();
-    let dir_context = match git::git_repository(&current_path) {
-        Some(repo) => {
-            db_find_or_create(tx, &curr_path_string, Some(repo.dir_name), repo.remote).await
-        }
-        None => db_find_or_create(tx, &curr_path_string, None, None).await,
-    }?;
+    context: &RuntimeDirContext,
+) -> Result<DirContext, DipsError> {
+    let dir_context = db_find_or_create(
+        tx,
+        &context.path(),
+        &context.subpath(),
+        context.git_dir_name.clone(),
+        context.git_remote.clone(),
+        context.match_dir_name,
+    )
+    .await?;
     Ok(dir_context)
 }
 
@@ -121,7 +145,13 @@ pub struct RuntimeDirContext {
     git_remote: Option<String>,
     git_dir_name: Option<String>,
     git_dir_path: Option<PathBuf>,
+    git_branch: Option<String>,
+    is_worktree: bool,
+    /// The repository the git repository is a submodule of.
+    superproject: Option<Box<git::GitRepository>>,
     path: PathBuf,
+    match_dir_name: bool,
+    inherit_superproject: bool,
 }
 
 impl RuntimeDirContext {
@@ -129,6 +159,10 @@ impl RuntimeDirContext {
         self.path.to_string_lossy().to_string()
     }
 
+    pub fn dir(&self) -> &Path {
+        &self.path
+    }
+
     pub fn git_dir(&self) -> Option<&str> {
         self.git_dir_name.as_deref()
     }
@@ -136,29 +170,96 @@ impl RuntimeDirContext {
     pub fn git_remote(&self) -> Option<&str> {
         self.git_remote.as_deref()
     }
-}
 
-impl TryFrom<&Path> for RuntimeDirContext {
-    type Error = Error;
+    /// The checked out branch of the git repository.
+    pub fn branch(&self) -> Option<&str> {
+        self.git_branch.as_deref()
+    }
 
-    fn try_from(path: &Path) -> Result<Self, Self::Error> {
-        let (git_remote, git_dir_name, git_dir_path) =
-            if let Some(repo) = git::git_repository(&path) {
-                (repo.remote, Some(repo.dir_name), Some(repo.path))
-            } else {
-                (None, None, None)
-            };
+    pub fn canonical_remote(&self) -> Option<String> {
+        self.git_remote.as_deref().and_then(git::canonical_remote)
+    }
 
-        if !path.exists() {
-            return Err(Error::new(ErrorKind::NotFound, "Incorrect context path"));
+    /// The path inside of the git repository with `/` separators, empty at
+    /// its root and outside of a repository.
+    pub fn subpath(&self) -> String {
+        relative_path(&self.path, self.git_dir_path.as_deref())
+    }
+
+    /// The git repository is a linked worktree.
+    pub fn is_worktree(&self) -> bool {
+        self.is_worktree
+    }
+
+    /// The git repository is a submodule of another one.
+    pub fn is_submodule(&self) -> bool {
+        self.superproject.is_some()
+    }
+
+    pub fn superproject_path(&self) -> Option<&Path> {
+        self.superproject.as_deref().map(|x| x.path.as_path())
+    }
+
+    fn superproject_remote(&self) -> Option<String> {
+        self.superproject
+            .as_deref()
+            .and_then(|x| x.canonical_remote())
+    }
+
+    /// The path inside of the superproject, like `subpath`.
+    fn superproject_subpath(&self) -> String {
+        relative_path(&self.path, self.superproject_path())
+    }
+
+    pub fn inherit_superproject(&self) -> bool {
+        self.inherit_superproject
+    }
+
+    /// Inherit the scopes of the superproject inside of a submodule, the
+    /// `inherit_superproject` in the config.
+    pub fn with_superproject_inheritance(self, inherit_superproject: bool) -> Self {
+        Self {
+            inherit_superproject,
+            ..self
         }
+    }
 
-        Ok(Self {
-            git_remote,
-            git_dir_name,
-            git_dir_path,
-            path: PathBuf::from(path),
+    pub fn match_dir_name(&self) -> bool {
+        self.match_dir_name
+    }
+
+    /// Match the scopes of the other repositories with the same directory
+    /// name, the `match_dir_name` in the config.
+    pub fn with_dir_name_matching(self, match_dir_name: bool) -> Self {
+        Self {
+            match_dir_name,
+            ..self
+        }
+    }
+
+    pub fn git_dir_path(&self) -> Option<&Path> {
+        self.git_dir_path.as_deref()
+    }
+}
+
+/// The path inside of the root with `/` separators, empty when it's not
+/// inside of it.
+fn relative_path(path: &Path, root: Option<&Path>) -> String {
+    root.and_then(|root| path.strip_prefix(root).ok())
+        .map(|x| {
+            x.components()
+                .map(|x| x.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
         })
+        .unwrap_or_default()
+}
+
+impl TryFrom<&Path> for RuntimeDirContext {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        Self::try_from(PathBuf::from(path))
     }
 }
 
@@ -166,116 +267,525 @@ impl TryFrom<PathBuf> for RuntimeDirContext {
     type Error = Error;
 
     fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
-        let (git_remote, git_dir_name, git_dir_path) =
-            if let Some(repo) = git::git_repository(&path) {
-                (repo.remote, Some(repo.dir_name), Some(repo.path))
-            } else {
-                (None, None, None)
-            };
-
         if !path.exists() {
             return Err(Error::new(ErrorKind::NotFound, "Incorrect context path"));
         }
+        let repo = git::git_repository(&path);
 
         Ok(Self {
-            git_remote,
-            git_dir_name,
-            git_dir_path,
+            git_remote: repo.as_ref().and_then(|x| x.remote.clone()),
+            git_dir_name: repo.as_ref().map(|x| x.dir_name.clone()),
+            git_branch: repo.as_ref().and_then(|x| x.branch.clone()),
+            is_worktree: repo.as_ref().is_some_and(|x| x.is_worktree),
+            git_dir_path: repo.as_ref().map(|x| x.path.clone()),
+            superproject: repo.and_then(|x| x.superproject),
             path,
+            match_dir_name: false,
+            inherit_superproject: false,
         })
     }
 }
 
-pub async fn db_find_one(
-    conn: &SqlitePool,
+/// Find the stored scope of the directory. The scope with the same remote
+/// and the same path inside of the repository wins, even when the repository
+/// is cloned in another directory, then the one with the same path. The
+/// repositories without a remote only match by their directory name when
+/// it's asked for, as the unrelated ones often share the same name.
+pub async fn db_find_one<'c, E>(
+    conn: E,
     current_path: &str,
+    subpath: &str,
     git_dir_name: Option<&str>,
     git_remote: Option<&str>,
-) -> Option<DirContext> {
-    match sqlx::query_as(
-        "SELECT * FROM dir_contexts WHERE dir_path = $1 OR git_remote = $2 OR git_dir_name = $3",
+    match_dir_name: bool,
+) -> Result<Option<DirContext>, sqlx::Error>
+where
+    E: sqlx::Executor<'c, Database = Sqlite>,
+{
+    let canonical_remote = git_remote.and_then(git::canonical_remote);
+    sqlx::query_as(
+        r"
+            select * from dir_contexts
+            where (canonical_remote = $2 and subpath = $5)
+            or dir_path = $1
+            or ($4 and $2 is null and git_dir_name = $3 and subpath = $5)
+            order by
+                case
+                    when canonical_remote = $2 and subpath = $5 then 0
+                    when dir_path = $1 then 1
+                    else 2
+                end,
+                dir_path = $1 desc,
+                length(dir_path)
+            limit 1
+        ",
     )
     .bind(current_path)
+    .bind(canonical_remote)
     .bind(git_dir_name)
-    .bind(git_remote)
+    .bind(match_dir_name)
+    .bind(subpath)
     .fetch_optional(conn)
     .await
-    {
-        Ok(res) => res,
-        Err(e) => {
-            eprintln!("ERROR: failed to query dir_contexts: {e}");
-            None
-        }
-    }
 }
 
 pub async fn db_create(
     tx: &mut Transaction<'_, Sqlite>,
     dir_path: &str,
+    subpath: &str,
     git_dir_name: Option<String>,
     git_remote: Option<String>,
 ) -> Result<DirContext, sqlx::Error> {
-    let dir_context = DirContext::new(dir_path, git_dir_name, git_remote);
-    // TODO: make the UUID into a string otherwise it stores as garbage.
-    let id = dir_context.id.to_string();
-    sqlx::query!(
+    let dir_context = DirContext::new(dir_path, subpath, git_dir_name, git_remote);
+    sqlx::query(
         r#"
         insert into dir_contexts(
-            id, dir_path, git_remote, git_dir_name, created_at, updated_at
+            id, dir_path, subpath, git_remote, canonical_remote, git_dir_name, created_at,
+            updated_at
         ) values (
-            $1, $2, $3, $4, $5, $6
+            $1, $2, $3, $4, $5, $6, $7, $8
         )"#,
-        id,
-        dir_context.dir_path,
-        dir_context.git_remote,
-        dir_context.git_dir_name,
-        dir_context.created_at,
-        dir_context.updated_at,
     )
+    .bind(dir_context.id.to_string())
+    .bind(&dir_context.dir_path)
+    .bind(&dir_context.subpath)
+    .bind(&dir_context.git_remote)
+    .bind(&dir_context.canonical_remote)
+    .bind(&dir_context.git_dir_name)
+    .bind(dir_context.created_at)
+    .bind(dir_context.updated_at)
     .execute(&mut **tx)
     .await?;
-    // TODO: if res.rows_affected() == 1
     Ok(dir_context)
 }
 
 pub async fn db_find_or_create(
     tx: &mut Transaction<'_, Sqlite>,
     current_path: &str,
+    subpath: &str,
     git_dir_name: Option<String>,
     git_remote: Option<String>,
+    match_dir_name: bool,
 ) -> Result<DirContext, sqlx::Error> {
-    if let Some(res) = sqlx::query_as(
-        "SELECT * FROM dir_contexts WHERE dir_path = $1 OR git_remote = $2 OR git_dir_name = $3",
+    let existing = db_find_one(
+        &mut **tx,
+        current_path,
+        subpath,
+        git_dir_name.as_deref(),
+        git_remote.as_deref(),
+        match_dir_name,
     )
-    .bind(current_path)
-    .bind(&git_dir_name)
-    .bind(&git_remote)
-    .fetch_optional(&mut **tx)
-    .await?
-    {
-        Ok(res)
-    } else {
-        db_create(tx, current_path, git_dir_name, git_remote).await
+    .await?;
+    match existing {
+        Some(res) => Ok(res),
+        None => db_create(tx, current_path, subpath, git_dir_name, git_remote).await,
+    }
+}
+
+/// Store the canonical form of the remotes added before it existed. The
+/// migrations can't parse the URLs, so it runs right after them and does
+/// nothing once all the remotes have it.
+pub async fn backfill_canonical_remotes(conn: &SqlitePool) -> Result<(), sqlx::Error> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        r"
+            select id, git_remote from dir_contexts
+            where git_remote is not null and canonical_remote is null
+        ",
+    )
+    .fetch_all(conn)
+    .await?;
+    for (id, git_remote) in rows {
+        let Some(canonical) = git::canonical_remote(&git_remote) else {
+            continue;
+        };
+        sqlx::query("update dir_contexts set canonical_remote = $2 where id = $1")
+            .bind(id)
+            .bind(canonical)
+            .execute(conn)
+            .await?;
     }
+    Ok(())
 }
 
+/// The most specific scope of the directory, the first one of its chain.
 pub async fn get_closest(
     conn: &SqlitePool,
     ctx: &RuntimeDirContext,
 ) -> Result<Option<DirContext>, sqlx::Error> {
-    let git_remote = ctx.git_remote();
+    let chain = get_scope_chain(conn, ctx, Some(0)).await?;
+    Ok(chain.into_iter().find_map(|x| match x.scope {
+        ContextScope::Dir(dir) => Some(dir),
+        ContextScope::Global => None,
+    }))
+}
+
+/// The closest scope of the git repository the directory is in, the one of
+/// the directory itself or of a parent inside of the repository, in any of
+/// its clones.
+pub async fn get_closest_in_repo(
+    conn: &SqlitePool,
+    ctx: &RuntimeDirContext,
+) -> Result<Option<DirContext>, sqlx::Error> {
+    let Some(git_root) = ctx.git_dir_path() else {
+        return Ok(None);
+    };
+    let canonical_remote = ctx.canonical_remote();
+    let chain = get_scope_chain(conn, ctx, None).await?;
+    Ok(chain.into_iter().find_map(|x| match x.scope {
+        ContextScope::Dir(dir)
+            if matches!(
+                x.origin,
+                ScopeOrigin::Current | ScopeOrigin::Parent | ScopeOrigin::Repository
+            ) && (Path::new(&dir.dir_path).starts_with(git_root)
+                || (canonical_remote.is_some() && dir.canonical_remote == canonical_remote)) =>
+        {
+            Some(dir)
+        }
+        _ => None,
+    }))
+}
+
+/// Where a scope in the inherited chain comes from, relative to the
+/// directory the command runs in.
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScopeOrigin {
+    Current,
+    Parent,
+    Repository,
+    /// The scopes of the repository a submodule is in.
+    Superproject,
+    /// The read-only dips from the file checked into the git repository.
+    RepoFile,
+    Global,
+}
+
+impl ScopeOrigin {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Current => "current",
+            Self::Parent => "parent",
+            Self::Repository => "repository",
+            Self::Superproject => "superproject",
+            Self::RepoFile => "repo file",
+            Self::Global => "global",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScopeLink {
+    pub origin: ScopeOrigin,
+    pub scope: ContextScope,
+}
+
+/// Resolve all the scopes the current directory inherits the dips from. The
+/// chain is ordered from the most specific scope to the least specific one:
+/// - the exact directory
+/// - the parent directories inside of the git repository
+/// - the git repository root and the scopes with the same remote
+/// - the superproject of a submodule
+/// - the parent directories outside of the git repository
+/// - the global scope
+///
+/// The scopes with the same remote match by their path inside of the
+/// repository, so the deepest one wins in every clone of a monorepo, and the
+/// ones of the sibling directories are left out.
+///
+/// A submodule only inherits the scopes of its superproject when it's asked
+/// for, they come right after the repository ones.
+///
+/// The depth limits how many scopes are included after the closest one.
+pub async fn get_scope_chain(
+    conn: &SqlitePool,
+    ctx: &RuntimeDirContext,
+    depth: Option<usize>,
+) -> Result<Vec<ScopeLink>, sqlx::Error> {
     let path = ctx.path();
+    let subpath = ctx.subpath();
+    let git_root = ctx.git_dir_path().map(|x| x.to_string_lossy().to_string());
+    let canonical_remote = ctx.canonical_remote();
+    let superproject = ctx
+        .superproject_path()
+        .map(|x| x.to_string_lossy().to_string());
+    let superproject_remote = ctx
+        .inherit_superproject()
+        .then(|| ctx.superproject_remote())
+        .flatten();
+    let candidates: Vec<DirContext> = sqlx::query_as(
+        r"
+            select * from dir_contexts
+            where dir_path = $1
+            or substr($1, 1, length(dir_path) + 1) = dir_path || '/'
+            or dir_path = $2
+            or (
+                canonical_remote = $3
+                and (
+                    subpath = ''
+                    or subpath = $4
+                    or substr($4, 1, length(subpath) + 1) = subpath || '/'
+                )
+            )
+            or (
+                canonical_remote = $5
+                and (
+                    subpath = ''
+                    or subpath = $6
+                    or substr($6, 1, length(subpath) + 1) = subpath || '/'
+                )
+            )
+        ",
+    )
+    .bind(&path)
+    .bind(&git_root)
+    .bind(&canonical_remote)
+    .bind(&subpath)
+    .bind(&superproject_remote)
+    .bind(ctx.superproject_subpath())
+    .fetch_all(conn)
+    .await?;
+
+    let inside_repo = |dir_path: &str| {
+        git_root
+            .as_deref()
+            .is_some_and(|root| dir_path.starts_with(&format!("{root}/")))
+    };
+    let inside_superproject = |dir_path: &str| {
+        superproject
+            .as_deref()
+            .is_some_and(|root| dir_path == root || dir_path.starts_with(&format!("{root}/")))
+    };
+    let mut ranked = candidates
+        .into_iter()
+        .filter_map(|dir| {
+            let is_ancestor = path.starts_with(&format!("{}/", dir.dir_path));
+            let same_remote =
+                dir.canonical_remote.is_some() && dir.canonical_remote == canonical_remote;
+            let superproject_remote_match = dir.canonical_remote.is_some()
+                && dir.canonical_remote == superproject_remote
+                && !same_remote;
+            // The scopes with an empty subpath cover the whole repository.
+            let in_subpath = same_remote && !dir.subpath.is_empty();
+            // The scope of the same place in another clone is the current one too.
+            let same_place = same_remote && dir.subpath == subpath;
+            let (rank, origin) = if dir.dir_path == path || same_place {
+                (0, ScopeOrigin::Current)
+            } else if (is_ancestor && inside_repo(&dir.dir_path)) || in_subpath {
+                (1, ScopeOrigin::Parent)
+            } else if Some(&dir.dir_path) == git_root.as_ref() || same_remote {
+                (2, ScopeOrigin::Repository)
+            } else if (is_ancestor && inside_superproject(&dir.dir_path))
+                || superproject_remote_match
+            {
+                if !ctx.inherit_superproject() {
+                    return None;
+                }
+                (3, ScopeOrigin::Superproject)
+            } else if is_ancestor {
+                (4, ScopeOrigin::Parent)
+            } else {
+                return None;
+            };
+            // The clones live in different directories, so the scopes of the
+            // repository compare by their path inside of it.
+            let depth = if in_subpath || superproject_remote_match {
+                dir.subpath.len()
+            } else if inside_repo(&dir.dir_path) {
+                dir.dir_path.len() - git_root.as_deref().map_or(0, |x| x.len() + 1)
+            } else if inside_superproject(&dir.dir_path) {
+                dir.dir_path.len() - superproject.as_deref().map_or(0, |x| x.len())
+            } else {
+                dir.dir_path.len()
+            };
+            Some((rank, depth, origin, dir))
+        })
+        .collect::<Vec<_>>();
+    // Within the same rank, the deeper directory is the more specific one.
+    ranked.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)));
+
+    let mut chain = ranked
+        .into_iter()
+        .map(|(_, _, origin, dir)| ScopeLink {
+            origin,
+            scope: ContextScope::Dir(dir),
+        })
+        .collect::<Vec<_>>();
+    chain.push(ScopeLink {
+        origin: ScopeOrigin::Global,
+        scope: ContextScope::Global,
+    });
+
+    if let Some(depth) = depth {
+        chain.truncate(depth + 1);
+    }
+    Ok(chain)
+}
+
+/// A scope with the number of its dips.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ScopeUsage {
+    #[sqlx(flatten)]
+    pub scope: DirContext,
+    pub count: i64,
+}
+
+pub async fn get_usage(conn: &SqlitePool) -> Result<Vec<ScopeUsage>, sqlx::Error> {
     sqlx::query_as(
         r"
-            select * from dir_contexts 
-            where git_remote = $1
-            or $2 like dir_path || '%'
-            order by length(dir_path) desc
+            select dir_contexts.*, count(dips.id) as count
+            from dir_contexts
+            left join dips on dips.dir_context_id = dir_contexts.id
+            group by dir_contexts.id
+            order by dir_contexts.dir_path
+        ",
+    )
+    .fetch_all(conn)
+    .await
+}
+
+pub async fn find_by_id(conn: &SqlitePool, id: &str) -> Result<Option<DirContext>, sqlx::Error> {
+    sqlx::query_as("select * from dir_contexts where id = $1")
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+}
+
+pub async fn find_by_path<'c, E>(conn: E, path: &str) -> Result<Option<DirContext>, sqlx::Error>
+where
+    E: sqlx::Executor<'c, Database = Sqlite>,
+{
+    sqlx::query_as("select * from dir_contexts where dir_path = $1")
+        .bind(path)
+        .fetch_optional(conn)
+        .await
+}
+
+/// Move the scope and the scopes nested in it from the old path to the new
+/// one, and return the moved scopes.
+pub async fn relocate(
+    tx: &mut Transaction<'_, Sqlite>,
+    old_path: &str,
+    new_path: &str,
+) -> Result<Vec<DirContext>, sqlx::Error> {
+    let now = chrono::Utc::now().naive_utc();
+    sqlx::query_as(
+        r"
+            update dir_contexts
+            set dir_path = $2 || substr(dir_path, length($1) + 1),
+                updated_at = $3
+            where dir_path = $1 or substr(dir_path, 1, length($1) + 1) = $1 || '/'
+            returning *
+        ",
+    )
+    .bind(old_path)
+    .bind(new_path)
+    .bind(now)
+    .fetch_all(&mut **tx)
+    .await
+}
+
+/// The first path `relocate` would move a scope to that another scope has
+/// already, if there is any.
+pub async fn find_relocate_conflict(
+    tx: &mut Transaction<'_, Sqlite>,
+    old_path: &str,
+    new_path: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r"
+            select target.dir_path
+            from dir_contexts moved
+            join dir_contexts target
+                on target.dir_path = $2 || substr(moved.dir_path, length($1) + 1)
+                and target.id != moved.id
+            where moved.dir_path = $1
+                or substr(moved.dir_path, 1, length($1) + 1) = $1 || '/'
+            order by target.dir_path
             limit 1
         ",
     )
-    .bind(git_remote)
-    .bind(path)
-    .fetch_optional(conn)
+    .bind(old_path)
+    .bind(new_path)
+    .fetch_optional(&mut **tx)
     .await
 }
+
+/// Keep the git details of the scope in line with the directory it's in,
+/// the ones the scopes are matched by. They are cleared without a directory
+/// to read them from.
+pub async fn set_git(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: &Uuid,
+    context: Option<&RuntimeDirContext>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r"
+            update dir_contexts
+            set git_dir_name = $2, git_remote = $3, canonical_remote = $4, subpath = $5
+            where id = $1
+        ",
+    )
+    .bind(id.to_string())
+    .bind(context.and_then(|x| x.git_dir()))
+    .bind(context.and_then(|x| x.git_remote()))
+    .bind(context.and_then(|x| x.canonical_remote()))
+    .bind(context.map(|x| x.subpath()).unwrap_or_default())
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// How many dips the scope has, the archived ones included.
+pub async fn count_dips(tx: &mut Transaction<'_, Sqlite>, id: &Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("select count(*) from dips where dir_context_id = $1")
+        .bind(id.to_string())
+        .fetch_one(&mut **tx)
+        .await
+}
+
+/// Delete the dips of the scope with their tags, and return how many of
+/// them were deleted.
+pub async fn delete_dips(tx: &mut Transaction<'_, Sqlite>, id: &Uuid) -> Result<u64, sqlx::Error> {
+    let id = id.to_string();
+    sqlx::query(
+        r"
+            delete from dips_tags
+            where dip_id in (select id from dips where dir_context_id = $1)
+        ",
+    )
+    .bind(&id)
+    .execute(&mut **tx)
+    .await?;
+    let res = sqlx::query("delete from dips where dir_context_id = $1")
+        .bind(&id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Turn the dips of the scope into global ones, and return how many of them
+/// were moved.
+pub async fn reassign_global(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: &Uuid,
+) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query("update dips set dir_context_id = null where dir_context_id = $1")
+        .bind(id.to_string())
+        .execute(&mut **tx)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Delete the scope, it must not have any dips left.
+pub async fn delete(tx: &mut Transaction<'_, Sqlite>, id: &Uuid) -> Result<(), sqlx::Error> {
+    let id = id.to_string();
+    sqlx::query("delete from placeholder_values where dir_context_id = $1")
+        .bind(&id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("delete from dir_contexts where id = $1")
+        .bind(&id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
@@ -1,3 +1,7 @@
+pub mod backup;
 pub mod dip;
-pub mod tag;
 pub mod dir_context;
+pub mod repo_file;
+pub mod tag;
+pub mod tag_expr;
+pub mod template;
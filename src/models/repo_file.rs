@@ -0,0 +1,166 @@
+use crate::models::dip::{DipRowFull, DipTags};
+use crate::models::dir_context::ScopeOrigin;
+use crate::models::tag::TagMeta;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// The file names we look for in the root of the git repository. The first
+/// one that exists wins.
+static FILE_NAMES: [&str; 3] = [".dips.yaml", ".dips.yml", ".dips.toml"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum RepoFileError {
+    #[error("Failed to access the repository file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse the repository file: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Failed to parse the repository file: {0}")]
+    TomlDe(#[from] toml::de::Error),
+    #[error("Failed to write the repository file: {0}")]
+    TomlSer(#[from] toml::ser::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepoFileFormat {
+    Yaml,
+    Toml,
+}
+
+impl RepoFileFormat {
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|x| x.to_str()) {
+            Some("toml") => Self::Toml,
+            _ => Self::Yaml,
+        }
+    }
+}
+
+/// A dip shared through the repository. It has no id or scope as it belongs
+/// to the repository it's checked into.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct RepoDip {
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct RepoFile {
+    #[serde(default)]
+    pub dips: Vec<RepoDip>,
+}
+
+impl RepoFile {
+    /// Add the dip unless the same value is already in the file.
+    pub fn push(&mut self, item: RepoDip) -> bool {
+        if self.dips.iter().any(|x| x.value == item.value) {
+            false
+        } else {
+            self.dips.push(item);
+            true
+        }
+    }
+}
+
+/// Find the existing repository file in the git root.
+pub fn find(git_root: &Path) -> Option<PathBuf> {
+    FILE_NAMES
+        .iter()
+        .map(|x| git_root.join(x))
+        .find(|x| x.is_file())
+}
+
+/// The path of the repository file to write to. Keep the existing one,
+/// otherwise default to yaml.
+pub fn path_or_default(git_root: &Path) -> PathBuf {
+    find(git_root).unwrap_or_else(|| git_root.join(FILE_NAMES[0]))
+}
+
+pub fn read(path: &Path) -> Result<RepoFile, RepoFileError> {
+    let content = std::fs::read_to_string(path)?;
+    let file = match RepoFileFormat::from_path(path) {
+        RepoFileFormat::Yaml => serde_yaml::from_str(&content)?,
+        RepoFileFormat::Toml => toml::from_str(&content)?,
+    };
+    Ok(file)
+}
+
+pub fn write(path: &Path, file: &RepoFile) -> Result<(), RepoFileError> {
+    let content = match RepoFileFormat::from_path(path) {
+        RepoFileFormat::Yaml => serde_yaml::to_string(file)?,
+        RepoFileFormat::Toml => toml::to_string_pretty(file)?,
+    };
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// The dips don't have an id in the file, so we derive a stable one from the
+/// file path and the value to tell them apart in the lists.
+fn stable_id(path: &Path, value: &str) -> Uuid {
+    let mut high = DefaultHasher::new();
+    (path, value).hash(&mut high);
+    let mut low = DefaultHasher::new();
+    (value, path).hash(&mut low);
+    Uuid::from_u64_pair(high.finish(), low.finish())
+}
+
+/// Turn the repository file dips into the rows we show next to the database
+/// dips. They are marked with the repository file origin as they are read-only.
+pub fn to_rows(path: &Path, file: RepoFile) -> Vec<DipRowFull> {
+    let modified = std::fs::metadata(path)
+        .and_then(|x| x.modified())
+        .map(chrono::DateTime::<chrono::Utc>::from)
+        .unwrap_or_else(|_| chrono::Utc::now())
+        .naive_utc();
+    // The rows belong to the repository root, which is also where they run.
+    let dir_path = path.parent().unwrap_or(path).display().to_string();
+    file.dips
+        .into_iter()
+        .map(|x| {
+            let tags = x
+                .tags
+                .into_iter()
+                .map(|name| TagMeta {
+                    id: stable_id(path, &name).to_string(),
+                    name,
+                })
+                .collect::<Vec<_>>();
+            DipRowFull {
+                id: stable_id(path, &x.value),
+                value: x.value,
+                note: x.note,
+                dir_context_id: None,
+                created_at: modified,
+                updated_at: modified,
+                git_remote: None,
+                git_dir_name: None,
+                dir_path: Some(dir_path.clone()),
+                branch: None,
+                tags: DipTags::from(tags),
+                snippet: None,
+                origin: Some(ScopeOrigin::RepoFile),
+            }
+        })
+        .collect()
+}
+
+/// Read the repository file rows for the git root if there is any file.
+pub fn get_rows(git_root: Option<&Path>) -> Result<Vec<DipRowFull>, RepoFileError> {
+    match git_root.and_then(find) {
+        Some(path) => Ok(to_rows(&path, read(&path)?)),
+        None => Ok(vec![]),
+    }
+}
+
+/// Put the repository file rows after the dips of the directory scopes and
+/// before the global ones.
+pub fn merge_rows(items: &mut Vec<DipRowFull>, rows: Vec<DipRowFull>) {
+    let at = items
+        .iter()
+        .position(|x| x.origin == Some(ScopeOrigin::Global))
+        .unwrap_or(items.len());
+    items.splice(at..at, rows);
+}
@@ -1,16 +1,16 @@
-use sqlx::{Sqlite, Transaction};
+use sqlx::{Sqlite, SqlitePool, Transaction};
 use uuid::Uuid;
 
 pub type Id = String;
 
-#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, Debug)]
 pub struct Tag {
     pub id: String,
     pub name: String,
     pub created_at: chrono::NaiveDateTime,
 }
 
-#[derive(Debug)]
+#[derive(serde::Serialize, Debug)]
 pub struct TagMeta {
     pub id: String,
     pub name: String,
@@ -59,7 +59,8 @@ pub async fn create_dip_tag(
     value: &str,
 ) -> Result<(), sqlx::Error> {
     let tag_id = get_or_create(tx, value).await?;
-    // TODO: make the UUID into a string otherwise it stores as garbage.
+    // Make the UUID into a string otherwise it stores as garbage.
+    let dip_id = dip_id.to_string();
     sqlx::query!(
         "insert into dips_tags (dip_id, tag_id) values($1, $2)",
         dip_id,
@@ -69,3 +70,137 @@ pub async fn create_dip_tag(
     .await?;
     Ok(())
 }
+
+/// Split the comma separated tag names. The empty names and the duplicates
+/// are left out.
+pub fn split_names(values: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for name in values.iter().flat_map(|x| x.split(',')).map(str::trim) {
+        if !name.is_empty() && !names.iter().any(|x| x == name) {
+            names.push(name.to_owned());
+        }
+    }
+    names
+}
+
+/// Remove the tag from the dip. The tag itself stays even if no dip uses it.
+pub async fn delete_dip_tag(
+    tx: &mut Transaction<'_, Sqlite>,
+    dip_id: &Uuid,
+    value: &str,
+) -> Result<bool, sqlx::Error> {
+    let dip_id = dip_id.to_string();
+    let res = sqlx::query(
+        r"
+        delete from dips_tags
+        where dip_id = $1
+          and tag_id = (select id from tags where name = $2)
+        ",
+    )
+    .bind(dip_id)
+    .bind(value)
+    .execute(&mut **tx)
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// How many dips use the tag in a scope. The unused tags have a single row
+/// with no scope and zero dips.
+#[derive(sqlx::FromRow, Debug)]
+pub struct TagUsage {
+    pub name: String,
+    pub dir_path: Option<String>,
+    pub global: bool,
+    pub count: i64,
+}
+
+pub async fn get_usage(conn: &SqlitePool) -> Result<Vec<TagUsage>, sqlx::Error> {
+    sqlx::query_as(
+        r"
+        select tags.name,
+            dir_contexts.dir_path,
+            dips.id is not null and dips.dir_context_id is null as global,
+            count(dips.id) as count
+        from tags
+        left join dips_tags on dips_tags.tag_id = tags.id
+        left join dips on dips.id = dips_tags.dip_id
+        left join dir_contexts on dir_contexts.id = dips.dir_context_id
+        group by tags.id, dips.dir_context_id
+        order by tags.name, global, dir_contexts.dir_path
+        ",
+    )
+    .fetch_all(conn)
+    .await
+}
+
+pub async fn find_id(
+    tx: &mut Transaction<'_, Sqlite>,
+    name: &str,
+) -> Result<Option<Id>, sqlx::Error> {
+    sqlx::query_scalar("select id from tags where name = $1")
+        .bind(name)
+        .fetch_optional(&mut **tx)
+        .await
+}
+
+pub async fn rename(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: &str,
+    name: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("update tags set name = $2 where id = $1")
+        .bind(id)
+        .bind(name)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Remove the tag together with its links to the dips. Returns how many dips
+/// lost the tag.
+pub async fn delete(tx: &mut Transaction<'_, Sqlite>, id: &str) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query("delete from dips_tags where tag_id = $1")
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("delete from tags where id = $1")
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Move the dips of the `from` tag to the `into` tag and remove the `from`
+/// tag. The dips that already have both tags keep a single link. Returns how
+/// many dips were moved.
+pub async fn merge(
+    tx: &mut Transaction<'_, Sqlite>,
+    from: &str,
+    into: &str,
+) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query(
+        r"
+        insert or ignore into dips_tags (dip_id, tag_id)
+        select dip_id, $2 from dips_tags where tag_id = $1
+        ",
+    )
+    .bind(from)
+    .bind(into)
+    .execute(&mut **tx)
+    .await?;
+    delete(tx, from).await?;
+    Ok(res.rows_affected())
+}
+
+/// Remove the tags no dip uses and return their names.
+pub async fn prune(tx: &mut Transaction<'_, Sqlite>) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r"
+        delete from tags
+        where id not in (select tag_id from dips_tags)
+        returning name
+        ",
+    )
+    .fetch_all(&mut **tx)
+    .await
+}
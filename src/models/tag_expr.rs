@@ -0,0 +1,238 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A boolean expression over the tag names, e.g. `db and (prod or staging)`
+/// or `deploy !legacy`. The words next to each other are joined with `and`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagExpr {
+    Tag(String),
+    Not(Box<TagExpr>),
+    And(Box<TagExpr>, Box<TagExpr>),
+    Or(Box<TagExpr>, Box<TagExpr>),
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum TagExprError {
+    #[error("The tag filter is empty")]
+    Empty,
+    #[error("Unexpected \"{0}\" in the tag filter")]
+    Unexpected(String),
+    #[error("The tag filter ended unexpectedly")]
+    UnexpectedEnd,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Not,
+    And,
+    Or,
+    Name(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open => write!(f, "("),
+            Self::Close => write!(f, ")"),
+            Self::Not => write!(f, "!"),
+            Self::And => write!(f, "and"),
+            Self::Or => write!(f, "or"),
+            Self::Name(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+fn is_operator(c: char) -> bool {
+    matches!(c, '(' | ')' | '!' | '&' | '|' | ',')
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '!' => tokens.push(Token::Not),
+            ',' => tokens.push(Token::And),
+            '&' | '|' => {
+                // Accept the doubled `&&` and `||` as well.
+                if chars.peek() == Some(&c) {
+                    chars.next();
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            c => {
+                let mut name = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || is_operator(c) {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                tokens.push(match name.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Name(name),
+                });
+            }
+        }
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<TagExpr, TagExprError> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = TagExpr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<TagExpr, TagExprError> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Name(_) | Token::Not | Token::Open) => {}
+                _ => break,
+            }
+            expr = TagExpr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<TagExpr, TagExprError> {
+        match self.next() {
+            Some(Token::Not) => Ok(TagExpr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    Some(token) => Err(TagExprError::Unexpected(token.to_string())),
+                    None => Err(TagExprError::UnexpectedEnd),
+                }
+            }
+            Some(Token::Name(name)) => Ok(TagExpr::Tag(name)),
+            Some(token) => Err(TagExprError::Unexpected(token.to_string())),
+            None => Err(TagExprError::UnexpectedEnd),
+        }
+    }
+}
+
+impl FromStr for TagExpr {
+    type Err = TagExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s);
+        if tokens.is_empty() {
+            return Err(TagExprError::Empty);
+        }
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let expr = parser.parse_or()?;
+        match parser.next() {
+            Some(token) => Err(TagExprError::Unexpected(token.to_string())),
+            None => Ok(expr),
+        }
+    }
+}
+
+impl fmt::Display for TagExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tag(name) => write!(f, "{name}"),
+            Self::Not(expr) => match **expr {
+                Self::Tag(_) | Self::Not(_) => write!(f, "!{expr}"),
+                _ => write!(f, "!({expr})"),
+            },
+            Self::And(left, right) => {
+                for (i, expr) in [left, right].into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " and ")?;
+                    }
+                    match **expr {
+                        Self::Or(..) => write!(f, "({expr})")?,
+                        _ => write!(f, "{expr}")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::Or(left, right) => write!(f, "{left} or {right}"),
+        }
+    }
+}
+
+impl TagExpr {
+    /// Join the expressions with `and`, so the repeated filters all have to
+    /// match.
+    pub fn all(exprs: Vec<TagExpr>) -> Option<TagExpr> {
+        exprs
+            .into_iter()
+            .reduce(|left, right| TagExpr::And(Box::new(left), Box::new(right)))
+    }
+
+    /// The SQL condition on the `dips` table. The tag names are pushed to
+    /// `names` and read from the json array bound to `param`, so the user
+    /// input never ends up in the query itself.
+    pub fn to_sql(&self, param: &str, names: &mut Vec<String>) -> String {
+        match self {
+            Self::Tag(name) => {
+                names.push(name.to_owned());
+                format!(
+                    "exists (select 1 from dips_tags dt join tags t on t.id = dt.tag_id \
+                     where dt.dip_id = dips.id and t.name = json_extract({param}, '$[{}]'))",
+                    names.len() - 1
+                )
+            }
+            Self::Not(expr) => format!("not ({})", expr.to_sql(param, names)),
+            Self::And(left, right) => format!(
+                "({}) and ({})",
+                left.to_sql(param, names),
+                right.to_sql(param, names)
+            ),
+            Self::Or(left, right) => format!(
+                "({}) or ({})",
+                left.to_sql(param, names),
+                right.to_sql(param, names)
+            ),
+        }
+    }
+
+    /// Evaluate the expression against the tag names of a dip that is not
+    /// in the database, like the ones from the repository file.
+    pub fn matches(&self, tags: &[&str]) -> bool {
+        match self {
+            Self::Tag(name) => tags.contains(&name.as_str()),
+            Self::Not(expr) => !expr.matches(tags),
+            Self::And(left, right) => left.matches(tags) && right.matches(tags),
+            Self::Or(left, right) => left.matches(tags) || right.matches(tags),
+        }
+    }
+}
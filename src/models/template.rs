@@ -0,0 +1,135 @@
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// A `{{name}}` or `{{name:default}}` part of a dip value that is filled in
+/// right before the dip is run or copied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placeholder {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+/// Go over the `{{...}}` parts of the value. The callback gets the inner text
+/// and returns the replacement, or `None` to keep the part as it is.
+fn replace_parts(value: &str, mut f: impl FnMut(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + 2 + end];
+        out.push_str(&rest[..start]);
+        match f(inner) {
+            Some(replacement) => out.push_str(&replacement),
+            None => out.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &rest[start + end + 4..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The names look like identifiers, so the other `{{...}}` syntaxes in the
+/// commands, e.g. the Go templates of `docker ps --format`, are left alone.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+        && chars.all(|x| x.is_ascii_alphanumeric() || x == '_' || x == '-')
+}
+
+fn parse_part(inner: &str) -> Option<Placeholder> {
+    let (name, default) = match inner.split_once(':') {
+        Some((name, default)) => (name.trim(), Some(default.to_owned())),
+        None => (inner.trim(), None),
+    };
+    if !is_name(name) {
+        return None;
+    }
+    Some(Placeholder {
+        name: name.to_owned(),
+        default,
+    })
+}
+
+/// The placeholders of the value in the order they first appear. A name used
+/// several times is only asked once, the first default wins.
+pub fn placeholders(value: &str) -> Vec<Placeholder> {
+    let mut items: Vec<Placeholder> = vec![];
+    replace_parts(value, |inner| {
+        if let Some(item) = parse_part(inner) {
+            match items.iter_mut().find(|x| x.name == item.name) {
+                Some(found) if found.default.is_none() => found.default = item.default,
+                Some(_) => {}
+                None => items.push(item),
+            }
+        }
+        None
+    });
+    items
+}
+
+pub fn is_template(value: &str) -> bool {
+    !placeholders(value).is_empty()
+}
+
+/// Fill the placeholders with the values. The ones without a value fall back
+/// to their default, or are left as they are.
+pub fn render(value: &str, values: &HashMap<String, String>) -> String {
+    replace_parts(value, |inner| {
+        let item = parse_part(inner)?;
+        values.get(&item.name).cloned().or(item.default)
+    })
+}
+
+/// The value to offer for the placeholder, the last one used wins over the
+/// default of the template.
+pub fn suggestion(item: &Placeholder, last: &HashMap<String, String>) -> Option<String> {
+    last.get(&item.name)
+        .cloned()
+        .or_else(|| item.default.clone())
+}
+
+/// The last values of the placeholders used in the scope. The global dips
+/// don't have any scope.
+pub async fn get_last_values(
+    conn: &SqlitePool,
+    dir_context_id: Option<&str>,
+) -> Result<HashMap<String, String>, sqlx::Error> {
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("select name, value from placeholder_values where dir_context_id is $1")
+            .bind(dir_context_id)
+            .fetch_all(conn)
+            .await?;
+    Ok(rows.into_iter().collect())
+}
+
+pub async fn save_values(
+    conn: &SqlitePool,
+    dir_context_id: Option<&str>,
+    values: &HashMap<String, String>,
+) -> Result<(), sqlx::Error> {
+    let now = chrono::Utc::now().naive_utc();
+    let mut tx = conn.begin().await?;
+    for (name, value) in values {
+        sqlx::query("delete from placeholder_values where dir_context_id is $1 and name = $2")
+            .bind(dir_context_id)
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "insert into placeholder_values (dir_context_id, name, value, updated_at) \
+             values ($1, $2, $3, $4)",
+        )
+        .bind(dir_context_id)
+        .bind(name)
+        .bind(value)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use color_eyre::{config::HookBuilder, eyre};
 // use ratatui::Show;
+use crate::configuration::ThemeName;
+use ratatui::style::palette::tailwind::{AMBER, GRAY, RED, SLATE, YELLOW};
+use ratatui::style::Color;
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::{
@@ -14,6 +17,63 @@ use std::panic;
 /// A type alias for the terminal type used in this application
 pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 
+/// The colors of the interface, picked by the `tui.theme` in the config.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Background of the prompt and the selected line.
+    pub bar: Color,
+    /// Background of the key hints in the prompt.
+    pub badge: Color,
+    /// The tags, remotes and the other secondary details.
+    pub border: Color,
+    pub text: Color,
+    pub key: Color,
+    pub muted: Color,
+    pub faint: Color,
+    /// The search matches and the other highlights.
+    pub accent: Color,
+    pub danger: Color,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            bar: SLATE.c800,
+            badge: SLATE.c600,
+            border: SLATE.c500,
+            text: GRAY.c200,
+            key: GRAY.c400,
+            muted: GRAY.c500,
+            faint: GRAY.c600,
+            accent: YELLOW.c400,
+            danger: RED.c500,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            bar: SLATE.c200,
+            badge: SLATE.c300,
+            border: SLATE.c500,
+            text: GRAY.c800,
+            key: GRAY.c600,
+            muted: GRAY.c500,
+            faint: GRAY.c400,
+            accent: AMBER.c600,
+            danger: RED.c600,
+        }
+    }
+}
+
+impl From<ThemeName> for Theme {
+    fn from(value: ThemeName) -> Self {
+        match value {
+            ThemeName::Dark => Self::dark(),
+            ThemeName::Light => Self::light(),
+        }
+    }
+}
+
 /// Initialize the terminal
 pub fn init() -> std::io::Result<Tui> {
     execute!(stdout(), EnterAlternateScreen)?;
@@ -1,6 +1,7 @@
 use crate::helpers::TestApp;
-use dips::commands::add;
-use dips::models::dip;
+use dips::commands::add::{self, AddOptions};
+use dips::error::DipsError;
+use dips::models::{dip, dir_context};
 use fake::faker::lorem::en::Word;
 use fake::Fake;
 
@@ -9,7 +10,9 @@ async fn adding_new_value_should_be_added() {
     let setup = TestApp::setup().await;
     let application = setup.application();
     let input = Word().fake();
-    add::add(application, input, None, false).await;
+    add::add(application, input, None, &[], &AddOptions::new())
+        .await
+        .unwrap();
 
     let rows = dip::get_all(&application.db_pool).await.unwrap();
     assert_eq!(rows.len(), 1);
@@ -23,7 +26,9 @@ async fn adding_new_value_should_have_correct_dir_context() {
     let setup = TestApp::setup().await;
     let application = setup.application();
     let input = Word().fake();
-    add::add(application, input, None, false).await;
+    add::add(application, input, None, &[], &AddOptions::new())
+        .await
+        .unwrap();
 
     let rows = dip::get_all(&application.db_pool).await.unwrap();
     assert_eq!(rows.len(), 1);
@@ -38,7 +43,15 @@ async fn adding_new_value_with_group_stores_group() {
     let application = setup.application();
     let input = Word().fake();
     let group: String = Word().fake();
-    add::add(application, input, Some(&group), false).await;
+    add::add(
+        application,
+        input,
+        None,
+        std::slice::from_ref(&group),
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
 
     let rows = dip::get_all(&application.db_pool).await.unwrap();
     assert_eq!(rows.len(), 1);
@@ -53,7 +66,15 @@ async fn adding_value_with_global_flag_stores_global() {
     let setup = TestApp::setup().await;
     let application = setup.application();
     let input = Word().fake();
-    add::add(application, input, None, true).await;
+    add::add(
+        application,
+        input,
+        None,
+        &[],
+        &AddOptions::new().with_global(true),
+    )
+    .await
+    .unwrap();
 
     let rows = dip::get_all(&application.db_pool).await.unwrap();
     assert_eq!(rows.len(), 1);
@@ -69,9 +90,12 @@ async fn add_existing_dir_context_value_complains() {
     let application = setup.application();
     let input = Word().fake();
 
-    add::add(application, input, None, false).await;
-    add::add(application, input, None, false).await;
+    add::add(application, input, None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+    let result = add::add(application, input, None, &[], &AddOptions::new()).await;
 
+    assert!(matches!(result, Err(DipsError::Validation(_))));
     let rows = dip::get_all(&application.db_pool).await.unwrap();
     assert_eq!(rows.len(), 1);
 }
@@ -83,9 +107,25 @@ async fn add_existing_dir_context_and_group_value_complains() {
     let input = Word().fake();
     let group: String = Word().fake();
 
-    add::add(application, input, Some(&group), false).await;
-    add::add(application, input, Some(&group), false).await;
+    add::add(
+        application,
+        input,
+        None,
+        std::slice::from_ref(&group),
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    let result = add::add(
+        application,
+        input,
+        None,
+        std::slice::from_ref(&group),
+        &AddOptions::new(),
+    )
+    .await;
 
+    assert!(matches!(result, Err(DipsError::Validation(_))));
     let rows = dip::get_all(&application.db_pool).await.unwrap();
     assert_eq!(rows.len(), 1);
 }
@@ -96,9 +136,25 @@ async fn add_existing_global_value_complains() {
     let application = setup.application();
     let input = Word().fake();
 
-    add::add(application, input, None, true).await;
-    add::add(application, input, None, true).await;
+    add::add(
+        application,
+        input,
+        None,
+        &[],
+        &AddOptions::new().with_global(true),
+    )
+    .await
+    .unwrap();
+    let result = add::add(
+        application,
+        input,
+        None,
+        &[],
+        &AddOptions::new().with_global(true),
+    )
+    .await;
 
+    assert!(matches!(result, Err(DipsError::Validation(_))));
     let rows = dip::get_all(&application.db_pool).await.unwrap();
     assert_eq!(rows.len(), 1);
 }
@@ -110,9 +166,149 @@ async fn add_existing_global_group_value_complains() {
     let input = Word().fake();
     let group: String = Word().fake();
 
-    add::add(application, input, Some(&group), true).await;
-    add::add(application, input, Some(&group), true).await;
+    add::add(
+        application,
+        input,
+        None,
+        std::slice::from_ref(&group),
+        &AddOptions::new().with_global(true),
+    )
+    .await
+    .unwrap();
+    let result = add::add(
+        application,
+        input,
+        None,
+        std::slice::from_ref(&group),
+        &AddOptions::new().with_global(true),
+    )
+    .await;
 
+    assert!(matches!(result, Err(DipsError::Validation(_))));
     let rows = dip::get_all(&application.db_pool).await.unwrap();
     assert_eq!(rows.len(), 1);
 }
+
+#[tokio::test]
+async fn adding_value_with_repeated_and_comma_tags_stores_all() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    let tags = vec![
+        "db,prod".to_string(),
+        "deploy".to_string(),
+        "db".to_string(),
+    ];
+    add::add(application, "psql", None, &tags, &AddOptions::new())
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    let mut names = rows[0]
+        .tags
+        .iter()
+        .map(|x| x.name.as_str())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["db", "deploy", "prod"]);
+}
+
+#[tokio::test]
+async fn adding_global_value_with_tag_stores_tag() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "htop",
+        None,
+        &["monitoring".to_string()],
+        &AddOptions::new().with_global(true),
+    )
+    .await
+    .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert!(rows[0].dir_context_id.is_none());
+    assert_eq!(rows[0].tags.len(), 1);
+    assert_eq!(rows[0].tags[0].name, "monitoring");
+}
+
+#[tokio::test]
+async fn adding_value_uses_the_scope_of_the_context_dir() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    let expected = setup.context_dir().canonicalize().unwrap();
+    assert_eq!(rows[0].dir_path.as_deref(), expected.to_str());
+}
+
+#[tokio::test]
+async fn adding_in_a_subdirectory_uses_the_repository_scope() {
+    let setup = TestApp::setup_in_repo("main", &[]).await;
+    let subdir = setup.context_dir().join("crates/cli");
+    std::fs::create_dir_all(&subdir).unwrap();
+    add::add(
+        setup.application(),
+        "cargo test",
+        None,
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    let nested = setup.application_in(&subdir);
+    add::add(&nested, "cargo run", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    let scopes = dir_context::get_usage(&nested.db_pool).await.unwrap();
+    assert_eq!(scopes.len(), 1);
+    assert_eq!(scopes[0].count, 2);
+    assert!(add::value_exists(&nested, "cargo test", &AddOptions::new())
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn adding_here_creates_a_scope_for_the_subdirectory() {
+    let setup = TestApp::setup_in_repo("main", &[]).await;
+    let subdir = setup.context_dir().join("crates/cli");
+    std::fs::create_dir_all(&subdir).unwrap();
+    add::add(
+        setup.application(),
+        "cargo test",
+        None,
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    let nested = setup.application_in(&subdir);
+    add::add(
+        &nested,
+        "cargo test",
+        None,
+        &[],
+        &AddOptions::new().with_here(true),
+    )
+    .await
+    .unwrap();
+
+    let mut subpaths = dir_context::get_usage(&nested.db_pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|x| (x.scope.subpath, x.count))
+        .collect::<Vec<_>>();
+    subpaths.sort();
+    assert_eq!(
+        subpaths,
+        vec![(String::new(), 1), ("crates/cli".to_string(), 1)]
+    );
+}
@@ -0,0 +1,222 @@
+use crate::helpers::TestApp;
+use dips::commands::export;
+use dips::models::backup::{self, BackupSelection, ImportMode};
+use dips::models::dir_context::{self, ContextScope};
+use dips::models::{dip, tag};
+
+async fn create_tagged(setup: &TestApp, path: &std::path::Path, value: &str, tags: &[&str]) {
+    let application = setup.application();
+    let mut tx = application.db_pool.begin().await.unwrap();
+    let scope =
+        dir_context::db_find_or_create(&mut tx, &path.display().to_string(), "", None, None, false)
+            .await
+            .unwrap();
+    let item = dip::create_with_transaction(&mut tx, Some(scope.id), value, None)
+        .await
+        .unwrap();
+    for name in tags {
+        tag::create_dip_tag(&mut tx, &item.id, name).await.unwrap();
+    }
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn backup_restores_into_empty_database_with_same_ids() {
+    let source = TestApp::setup().await;
+    let root = tempfile::TempDir::new().unwrap();
+    create_tagged(&source, root.path(), "make deploy", &["deploy", "prod"]).await;
+    dip::create(&source.application().db_pool, None, "htop", None)
+        .await
+        .unwrap();
+    let exported = backup::get_backup(&source.application().db_pool, &BackupSelection::All)
+        .await
+        .unwrap();
+    let json = serde_json::to_string(&exported).unwrap();
+
+    let target = TestApp::setup().await;
+    let pool = &target.application().db_pool;
+    let mut tx = pool.begin().await.unwrap();
+    let summary = backup::restore(
+        &mut tx,
+        &serde_json::from_str(&json).unwrap(),
+        ImportMode::Merge,
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!(
+        (summary.dips, summary.dir_contexts, summary.tags),
+        (2, 1, 2)
+    );
+    let before = dip::get_all(&source.application().db_pool).await.unwrap();
+    let after = dip::get_all(pool).await.unwrap();
+    assert_eq!(
+        before.iter().map(|x| x.id).collect::<Vec<_>>(),
+        after.iter().map(|x| x.id).collect::<Vec<_>>()
+    );
+    let deploy = after.iter().find(|x| x.value == "make deploy").unwrap();
+    assert_eq!(deploy.tags.len(), 2);
+    assert_eq!(deploy.dir_path, Some(root.path().display().to_string()));
+}
+
+#[tokio::test]
+async fn backup_merge_skips_values_already_in_the_scope() {
+    let setup = TestApp::setup().await;
+    let pool = &setup.application().db_pool;
+    let root = tempfile::TempDir::new().unwrap();
+    create_tagged(&setup, root.path(), "make deploy", &[]).await;
+    let exported = backup::get_backup(pool, &BackupSelection::All)
+        .await
+        .unwrap();
+
+    // Same value in the same directory, but with a different id.
+    let other = TestApp::setup().await;
+    create_tagged(&other, root.path(), "make deploy", &[]).await;
+    create_tagged(&other, root.path(), "make build", &[]).await;
+    let incoming = backup::get_backup(&other.application().db_pool, &BackupSelection::All)
+        .await
+        .unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let summary = backup::restore(&mut tx, &incoming, ImportMode::Merge)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!((summary.dips, summary.skipped), (1, 1));
+    assert_eq!(summary.dir_contexts, 0);
+    let rows = dip::get_all(pool).await.unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().any(|x| x.id == exported.dips[0].id));
+}
+
+#[tokio::test]
+async fn backup_matches_the_scopes_of_another_clone_by_the_remote() {
+    let setup = TestApp::setup().await;
+    let pool = &setup.application().db_pool;
+    let remote = Some("git@github.com:owner/api.git".to_string());
+    let mut tx = pool.begin().await.unwrap();
+    let scope = dir_context::db_create(&mut tx, "/work/api", "web", None, remote.clone())
+        .await
+        .unwrap();
+    dip::create_with_transaction(&mut tx, Some(scope.id), "npm test", None)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let other = TestApp::setup().await;
+    let other_pool = &other.application().db_pool;
+    let mut tx = other_pool.begin().await.unwrap();
+    let cloned = dir_context::db_create(&mut tx, "/clones/api", "web", None, remote)
+        .await
+        .unwrap();
+    for value in ["npm test", "npm run lint"] {
+        dip::create_with_transaction(&mut tx, Some(cloned.id), value, None)
+            .await
+            .unwrap();
+    }
+    tx.commit().await.unwrap();
+    let incoming = backup::get_backup(other_pool, &BackupSelection::All)
+        .await
+        .unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let summary = backup::restore(&mut tx, &incoming, ImportMode::Merge)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!((summary.dips, summary.skipped), (1, 1));
+    assert_eq!(summary.dir_contexts, 0);
+    let scopes = dir_context::get_usage(pool).await.unwrap();
+    assert_eq!(scopes.len(), 1);
+    assert_eq!(scopes[0].count, 2);
+}
+
+#[tokio::test]
+async fn backup_replace_removes_existing_data() {
+    let setup = TestApp::setup().await;
+    let pool = &setup.application().db_pool;
+    dip::create(pool, None, "old value", None).await.unwrap();
+
+    let other = TestApp::setup().await;
+    dip::create(&other.application().db_pool, None, "new value", None)
+        .await
+        .unwrap();
+    let incoming = backup::get_backup(&other.application().db_pool, &BackupSelection::All)
+        .await
+        .unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    backup::restore(&mut tx, &incoming, ImportMode::Replace)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let rows = dip::get_all(pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, "new value");
+}
+
+#[tokio::test]
+async fn scoped_backup_replace_keeps_the_other_scopes() {
+    let setup = TestApp::setup().await;
+    let pool = &setup.application().db_pool;
+    let (first, second) = (
+        tempfile::TempDir::new().unwrap(),
+        tempfile::TempDir::new().unwrap(),
+    );
+    create_tagged(&setup, first.path(), "make deploy", &[]).await;
+    create_tagged(&setup, second.path(), "make build", &[]).await;
+    dip::create(pool, None, "htop", None).await.unwrap();
+    let scope = dir_context::find_by_path(pool, &first.path().display().to_string())
+        .await
+        .unwrap()
+        .unwrap();
+    let incoming = backup::get_backup(pool, &BackupSelection::Scope(ContextScope::Dir(scope)))
+        .await
+        .unwrap();
+    create_tagged(&setup, first.path(), "make later", &[]).await;
+
+    let mut tx = pool.begin().await.unwrap();
+    backup::restore(&mut tx, &incoming, ImportMode::Replace)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let mut values = dip::get_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|x| x.value)
+        .collect::<Vec<_>>();
+    values.sort();
+    assert_eq!(values, vec!["htop", "make build", "make deploy"]);
+}
+
+#[tokio::test]
+async fn backup_markdown_groups_by_scope_and_tag() {
+    let setup = TestApp::setup().await;
+    let pool = &setup.application().db_pool;
+    let root = tempfile::TempDir::new().unwrap();
+    create_tagged(&setup, root.path(), "make deploy", &["deploy"]).await;
+    create_tagged(&setup, root.path(), "make", &[]).await;
+    dip::create(pool, None, "htop", None).await.unwrap();
+
+    let all = backup::get_backup(pool, &BackupSelection::All)
+        .await
+        .unwrap();
+    let md = export::render_markdown(&all);
+    let expected = format!(
+        "# Dips\n\n## {}\n\n### deploy\n\n- `make deploy`\n\n### Untagged\n\n- `make`\n\n## Global\n\n### Untagged\n\n- `htop`\n",
+        root.path().display()
+    );
+    assert_eq!(md, expected);
+
+    let global = backup::get_backup(pool, &BackupSelection::Scope(ContextScope::Global))
+        .await
+        .unwrap();
+    assert_eq!(global.dips.len(), 1);
+    assert!(global.dir_contexts.is_empty() && global.tags.is_empty());
+}
@@ -0,0 +1,163 @@
+use crate::helpers::TestApp;
+use dips::commands::{
+    add::{self, AddOptions},
+    branch_gc,
+};
+use dips::error::DipsError;
+use dips::models::dip::{self, DipsFilter};
+use dips::models::dir_context;
+use std::path::Path;
+
+const REMOTE: &str = "git@github.com:owner/api.git";
+
+/// Another clone of the repository, with its own branch checked out.
+fn clone_on_branch(dir: &Path, branch: &str) {
+    let repo = git2::Repository::init(dir).unwrap();
+    repo.remote("origin", REMOTE).unwrap();
+    repo.set_head(&format!("refs/heads/{branch}")).unwrap();
+    let signature = git2::Signature::now("dips", "dips@example.com").unwrap();
+    let tree_id = repo.index().unwrap().write_tree().unwrap();
+    let tree = repo.find_tree(tree_id).unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+        .unwrap();
+}
+
+async fn visible_values(setup: &TestApp) -> Vec<String> {
+    let application = setup.application();
+    let chain = dir_context::get_scope_chain(&application.db_pool, &application.context_dir, None)
+        .await
+        .unwrap();
+    let filter = DipsFilter::new().with_branch(application.context_dir.branch());
+    let mut values = dip::get_inherited(&application.db_pool, &chain, filter)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|x| x.value)
+        .collect::<Vec<_>>();
+    values.sort();
+    values
+}
+
+#[tokio::test]
+async fn branch_dips_only_show_on_their_branch() {
+    let setup = TestApp::setup_in_repo("feature", &["main"]).await;
+    let application = setup.application();
+    let branch = add::branch_name(application, None).unwrap();
+    add::add(
+        application,
+        "rebase",
+        None,
+        &[],
+        &AddOptions::new().with_branch(Some(&branch)),
+    )
+    .await
+    .unwrap();
+    add::add(
+        application,
+        "release",
+        None,
+        &[],
+        &AddOptions::new().with_branch(Some("main")),
+    )
+    .await
+    .unwrap();
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    assert_eq!(branch, "feature");
+    assert_eq!(visible_values(&setup).await, vec!["cargo test", "rebase"]);
+}
+
+#[tokio::test]
+async fn branch_gc_archives_the_dips_of_deleted_branches() {
+    let setup = TestApp::setup_in_repo("main", &["kept"]).await;
+    let application = setup.application();
+    for branch in ["main", "kept", "gone"] {
+        add::add(
+            application,
+            branch,
+            None,
+            &[],
+            &AddOptions::new().with_branch(Some(branch)),
+        )
+        .await
+        .unwrap();
+    }
+
+    branch_gc::exec(application).await.unwrap();
+
+    let mut values = dip::get_filtered(&application.db_pool, DipsFilter::new().with_any_scope())
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|x| x.value)
+        .collect::<Vec<_>>();
+    values.sort();
+    assert_eq!(values, vec!["kept", "main"]);
+}
+
+#[tokio::test]
+async fn branch_gc_leaves_the_other_clones_alone() {
+    let setup = TestApp::setup_in_repo("main", &[]).await;
+    let repo = git2::Repository::open(setup.context_dir()).unwrap();
+    repo.remote("origin", REMOTE).unwrap();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("linked", &head, false).unwrap();
+    let others = tempfile::TempDir::new().unwrap();
+    let worktree = others.path().join("linked");
+    let linked = repo.find_reference("refs/heads/linked").unwrap();
+    let mut options = git2::WorktreeAddOptions::new();
+    repo.worktree("linked", &worktree, Some(options.reference(Some(&linked))))
+        .unwrap();
+    let clone = others.path().join("clone");
+    std::fs::create_dir_all(clone.join("web")).unwrap();
+    clone_on_branch(&clone, "feature");
+
+    let application = setup.application_in(setup.context_dir());
+    let worktree = setup.application_in(&worktree);
+    for branch in ["linked", "gone"] {
+        add::add(
+            &worktree,
+            branch,
+            None,
+            &[],
+            &AddOptions::new().with_branch(Some(branch)),
+        )
+        .await
+        .unwrap();
+    }
+    // The scope of a subpath of the other clone has the same remote.
+    let clone = setup.application_in(&clone.join("web"));
+    add::add(
+        &clone,
+        "feature",
+        None,
+        &[],
+        &AddOptions::new()
+            .with_branch(Some("feature"))
+            .with_here(true),
+    )
+    .await
+    .unwrap();
+
+    branch_gc::exec(&application).await.unwrap();
+
+    let mut values = dip::get_filtered(&application.db_pool, DipsFilter::new().with_any_scope())
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|x| x.value)
+        .collect::<Vec<_>>();
+    values.sort();
+    assert_eq!(values, vec!["feature", "linked"]);
+}
+
+#[tokio::test]
+async fn branch_needs_a_git_repository() {
+    let setup = TestApp::setup().await;
+
+    let result = add::branch_name(setup.application(), Some("feature".to_string()));
+
+    assert!(matches!(result, Err(DipsError::Validation(_))));
+}
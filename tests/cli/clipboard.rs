@@ -0,0 +1,33 @@
+use dips::clipboard::{self, ClipboardBackend, CopyMethod};
+
+#[test]
+fn osc52_carries_the_base64_value() {
+    let sequence = clipboard::osc52("cargo test");
+    assert!(sequence.contains("\x1b]52;c;Y2FyZ28gdGVzdA==\x07"));
+}
+
+#[test]
+fn auto_backend_falls_back_to_the_terminal() {
+    assert_eq!(
+        clipboard::methods(ClipboardBackend::Auto, true),
+        [CopyMethod::System, CopyMethod::Terminal]
+    );
+    assert_eq!(
+        clipboard::methods(ClipboardBackend::Auto, false),
+        [CopyMethod::Terminal]
+    );
+}
+
+#[test]
+fn explicit_backends_ignore_the_display() {
+    for has_display in [true, false] {
+        assert_eq!(
+            clipboard::methods(ClipboardBackend::System, has_display),
+            [CopyMethod::System]
+        );
+        assert_eq!(
+            clipboard::methods(ClipboardBackend::Osc52, has_display),
+            [CopyMethod::Terminal]
+        );
+    }
+}
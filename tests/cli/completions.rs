@@ -0,0 +1,78 @@
+use crate::helpers::TestApp;
+use clap_complete::CompletionCandidate;
+use dips::commands::{
+    add::{self, AddOptions},
+    completions,
+};
+use dips::models::dir_context::RuntimeDirContext;
+
+fn values(candidates: &[CompletionCandidate]) -> Vec<String> {
+    let mut values = candidates
+        .iter()
+        .map(|x| x.get_value().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    values.sort();
+    values
+}
+
+#[tokio::test]
+async fn selector_candidates_come_from_the_current_scope() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "cargo test",
+        Some("all tests"),
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    add::add(
+        application,
+        "git status",
+        None,
+        &[],
+        &AddOptions::new().with_global(true),
+    )
+    .await
+    .unwrap();
+
+    let candidates =
+        completions::selector_candidates(&application.db_pool, &application.context_dir).await;
+    assert_eq!(values(&candidates), ["cargo test", "git status"]);
+    let help = candidates
+        .iter()
+        .find(|x| x.get_value() == "cargo test")
+        .and_then(|x| x.get_help())
+        .map(ToString::to_string);
+    assert_eq!(help.as_deref(), Some("all tests"));
+
+    let elsewhere = tempfile::TempDir::new().unwrap();
+    let context_dir = RuntimeDirContext::try_from(elsewhere.path()).unwrap();
+    let candidates = completions::selector_candidates(&application.db_pool, &context_dir).await;
+    assert_eq!(values(&candidates), ["git status"]);
+}
+
+#[tokio::test]
+async fn tag_candidates_are_the_tag_names() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    let tags = ["rust".to_string(), "db".to_string()];
+    add::add(application, "cargo test", None, &tags, &AddOptions::new())
+        .await
+        .unwrap();
+    add::add(
+        application,
+        "sqlx migrate run",
+        None,
+        &tags[1..],
+        &AddOptions::new().with_global(true),
+    )
+    .await
+    .unwrap();
+
+    let candidates = completions::tag_candidates(&application.db_pool).await;
+
+    assert_eq!(values(&candidates), ["db", "rust"]);
+}
@@ -0,0 +1,46 @@
+use dips::commands::config;
+use dips::configuration::{ThemeName, UserSettings};
+
+#[test]
+fn set_value_creates_the_tables() {
+    let content = config::set_value("", "tui.keys.up", "p").unwrap();
+    let content = config::set_value(&content, "depth", "2").unwrap();
+
+    let settings = toml::from_str::<UserSettings>(&content).unwrap();
+    assert_eq!(settings.tui.keys.up, 'p');
+    assert_eq!(settings.tui.keys.down, 'j');
+    assert_eq!(settings.depth, Some(2));
+}
+
+#[test]
+fn set_value_keeps_the_comments() {
+    let content = "# My dips\n[tui]\ntheme = \"dark\"\n";
+    let updated = config::set_value(content, "tui.theme", "light").unwrap();
+
+    assert!(updated.starts_with("# My dips\n"));
+    let settings = toml::from_str::<UserSettings>(&updated).unwrap();
+    assert_eq!(settings.tui.theme, ThemeName::Light);
+}
+
+#[test]
+fn set_value_rejects_unknown_keys_and_invalid_values() {
+    assert!(matches!(
+        config::set_value("", "colour", "red"),
+        Err(config::SetError::UnknownKey(_))
+    ));
+    assert!(matches!(
+        config::set_value("", "format", "xml"),
+        Err(config::SetError::Invalid { .. })
+    ));
+}
+
+#[test]
+fn lookup_reads_the_dotted_keys() {
+    let settings = UserSettings::default();
+
+    assert_eq!(
+        config::lookup(&settings, "tui.keys.copy"),
+        Some(toml::Value::String("y".to_string()))
+    );
+    assert_eq!(config::lookup(&settings, "editor"), None);
+}
@@ -0,0 +1,223 @@
+use crate::helpers::TestApp;
+use dips::models::dir_context::{self, DirContext, RuntimeDirContext, ScopeOrigin};
+use std::path::{Path, PathBuf};
+
+async fn find_or_create(
+    setup: &TestApp,
+    path: &str,
+    subpath: &str,
+    git_dir_name: Option<&str>,
+    git_remote: Option<&str>,
+    match_dir_name: bool,
+) -> DirContext {
+    let mut tx = setup.application().db_pool.begin().await.unwrap();
+    let scope = dir_context::db_find_or_create(
+        &mut tx,
+        path,
+        subpath,
+        git_dir_name.map(String::from),
+        git_remote.map(String::from),
+        match_dir_name,
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+    scope
+}
+
+#[tokio::test]
+async fn ssh_and_https_remotes_share_the_scope() {
+    let setup = TestApp::setup().await;
+    let ssh = "git@github.com:owner/api.git";
+    let https = "https://github.com/owner/api";
+
+    let first = find_or_create(&setup, "/work/api", "", Some("api"), Some(ssh), false).await;
+    let second = find_or_create(&setup, "/clones/api", "", Some("api"), Some(https), false).await;
+
+    assert_eq!(first.id, second.id);
+    assert_eq!(
+        first.canonical_remote.as_deref(),
+        Some("github.com/owner/api")
+    );
+}
+
+#[tokio::test]
+async fn remote_wins_over_the_path() {
+    let setup = TestApp::setup().await;
+    let remote = "git@github.com:owner/api.git";
+    let other = "git@github.com:owner/web.git";
+    let repo = find_or_create(&setup, "/work/api", "", Some("api"), Some(remote), false).await;
+    find_or_create(&setup, "/clones/api", "", Some("api"), Some(other), false).await;
+
+    let found = dir_context::db_find_one(
+        &setup.application().db_pool,
+        "/clones/api",
+        "",
+        Some("api"),
+        Some(remote),
+        false,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(found.map(|x| x.id), Some(repo.id));
+}
+
+#[tokio::test]
+async fn same_dir_name_only_matches_when_asked() {
+    let setup = TestApp::setup().await;
+    let first = find_or_create(&setup, "/work/api", "", Some("api"), None, false).await;
+
+    let unrelated = find_or_create(&setup, "/other/api", "", Some("api"), None, false).await;
+    let asked = find_or_create(&setup, "/third/api", "", Some("api"), None, true).await;
+
+    assert_ne!(unrelated.id, first.id);
+    assert!([first.id, unrelated.id].contains(&asked.id));
+}
+
+#[tokio::test]
+async fn backfill_stores_the_canonical_remotes() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    let scope = find_or_create(
+        &setup,
+        "/work/api",
+        "",
+        Some("api"),
+        Some("git@gitlab.com:group/api.git"),
+        false,
+    )
+    .await;
+    sqlx::query("update dir_contexts set canonical_remote = null")
+        .execute(&application.db_pool)
+        .await
+        .unwrap();
+
+    dir_context::backfill_canonical_remotes(&application.db_pool)
+        .await
+        .unwrap();
+
+    let stored = dir_context::find_by_path(&application.db_pool, &scope.dir_path)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        stored.canonical_remote.as_deref(),
+        Some("gitlab.com/group/api")
+    );
+}
+
+/// A clone of a monorepo with the `services/billing` and `web` directories.
+fn clone_monorepo(dir: &Path) -> PathBuf {
+    let dir = dir.canonicalize().unwrap();
+    let repo = git2::Repository::init(&dir).unwrap();
+    repo.remote("origin", "git@github.com:owner/mono.git")
+        .unwrap();
+    for x in ["services/billing/src", "web"] {
+        std::fs::create_dir_all(dir.join(x)).unwrap();
+    }
+    dir
+}
+
+async fn current_scope(setup: &TestApp, dir: PathBuf) -> DirContext {
+    let context = RuntimeDirContext::try_from(dir).unwrap();
+    let mut tx = setup.application().db_pool.begin().await.unwrap();
+    let scope = dir_context::get_or_create_current(&mut tx, &context)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    scope
+}
+
+#[tokio::test]
+async fn monorepo_scopes_match_by_the_subpath_in_every_clone() {
+    let setup = TestApp::setup().await;
+    let conn = &setup.application().db_pool;
+    let other = tempfile::TempDir::new().unwrap();
+    let first = clone_monorepo(setup.context_dir());
+    let second = clone_monorepo(other.path());
+
+    let billing = current_scope(&setup, first.join("services/billing")).await;
+    let cloned = current_scope(&setup, second.join("services/billing")).await;
+    let web = RuntimeDirContext::try_from(second.join("web")).unwrap();
+
+    assert_eq!(billing.subpath, "services/billing");
+    assert_eq!(cloned.id, billing.id);
+    assert!(dir_context::get_closest(conn, &web)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn deepest_subpath_comes_first_in_the_chain() {
+    let setup = TestApp::setup().await;
+    let other = tempfile::TempDir::new().unwrap();
+    let first = clone_monorepo(setup.context_dir());
+    let second = clone_monorepo(other.path());
+    let root = current_scope(&setup, first.clone()).await;
+    let billing = current_scope(&setup, first.join("services/billing")).await;
+
+    let context = RuntimeDirContext::try_from(second.join("services/billing/src")).unwrap();
+    let chain = dir_context::get_scope_chain(&setup.application().db_pool, &context, None)
+        .await
+        .unwrap();
+
+    let links = chain
+        .iter()
+        .map(|x| (x.origin, x.scope.id()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        links,
+        vec![
+            (ScopeOrigin::Parent, Some(billing.id)),
+            (ScopeOrigin::Repository, Some(root.id)),
+            (ScopeOrigin::Global, None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn submodule_only_inherits_the_superproject_when_asked() {
+    let setup = TestApp::setup().await;
+    let root = setup.context_dir().canonicalize().unwrap();
+    let repo = git2::Repository::init(&root).unwrap();
+    repo.submodule("https://github.com/owner/lib.git", Path::new("lib"), true)
+        .unwrap();
+    let superproject = current_scope(&setup, root.clone()).await;
+    let submodule = current_scope(&setup, root.join("lib")).await;
+    let conn = &setup.application().db_pool;
+
+    let isolated = RuntimeDirContext::try_from(root.join("lib")).unwrap();
+    let inheriting = RuntimeDirContext::try_from(root.join("lib"))
+        .unwrap()
+        .with_superproject_inheritance(true);
+    let isolated = dir_context::get_scope_chain(conn, &isolated, None)
+        .await
+        .unwrap();
+    let inheriting = dir_context::get_scope_chain(conn, &inheriting, None)
+        .await
+        .unwrap();
+
+    let links = |chain: &[dir_context::ScopeLink]| {
+        chain
+            .iter()
+            .map(|x| (x.origin, x.scope.id()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        links(&isolated),
+        vec![
+            (ScopeOrigin::Current, Some(submodule.id)),
+            (ScopeOrigin::Global, None),
+        ]
+    );
+    assert_eq!(
+        links(&inheriting),
+        vec![
+            (ScopeOrigin::Current, Some(submodule.id)),
+            (ScopeOrigin::Superproject, Some(superproject.id)),
+            (ScopeOrigin::Global, None),
+        ]
+    );
+}
@@ -0,0 +1,110 @@
+use crate::helpers::TestApp;
+use dips::commands::{
+    add::{self, AddOptions},
+    edit,
+};
+use dips::models::dip;
+
+#[tokio::test]
+async fn editing_value_updates_the_dip() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(application, "cargo tset", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    edit::exec(application, "cargo tset", Some("cargo test"), None)
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, "cargo test");
+    assert_eq!(rows[0].note, None);
+}
+
+#[tokio::test]
+async fn editing_note_keeps_the_value() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    edit::exec(application, "cargo test", None, Some("run before push"))
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, "cargo test");
+    assert_eq!(rows[0].note.as_deref(), Some("run before push"));
+}
+
+#[tokio::test]
+async fn editing_with_an_empty_note_removes_it() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "cargo test",
+        Some("run before push"),
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    edit::exec(application, "cargo test", None, Some(""))
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows[0].note, None);
+}
+
+#[test]
+fn tui_value_is_trimmed_and_not_empty() {
+    assert_eq!(edit::parse_value("  cargo test \n"), Some("cargo test"));
+    assert_eq!(edit::parse_value(" \t "), None);
+}
+
+#[test]
+fn tui_note_change_is_only_reported_when_it_differs() {
+    assert_eq!(edit::note_change(Some("old"), "old\n"), None);
+    assert_eq!(edit::note_change(None, "\n"), None);
+    assert_eq!(
+        edit::note_change(Some("old"), "new\n"),
+        Some(Some("new".to_string()))
+    );
+    assert_eq!(edit::note_change(Some("old"), "  \n"), Some(None));
+}
+
+#[tokio::test]
+async fn tui_edits_update_the_value_and_the_note() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "cargo tset",
+        Some("old"),
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    let item = &dip::get_all(&application.db_pool).await.unwrap()[0];
+
+    let value = edit::parse_value(" cargo test ").unwrap();
+    dip::update(&application.db_pool, &item.id, Some(value), None)
+        .await
+        .unwrap();
+    let note = edit::note_change(item.note.as_deref(), "\n").unwrap();
+    dip::update(&application.db_pool, &item.id, None, Some(note.as_deref()))
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows[0].value, "cargo test");
+    assert_eq!(rows[0].note, None);
+}
@@ -0,0 +1,161 @@
+use crate::helpers::TestApp;
+use dips::commands::get::{self, OutputFormat};
+use dips::models::dip;
+use dips::models::dir_context::{self, RuntimeDirContext, ScopeOrigin};
+
+async fn create_scope(setup: &TestApp, path: &std::path::Path, value: &str) {
+    let application = setup.application();
+    std::fs::create_dir_all(path).unwrap();
+    let mut tx = application.db_pool.begin().await.unwrap();
+    let scope =
+        dir_context::db_find_or_create(&mut tx, &path.display().to_string(), "", None, None, false)
+            .await
+            .unwrap();
+    dip::create_with_transaction(&mut tx, Some(scope.id), value, None)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn scope_chain_goes_from_closest_to_global() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    let root = tempfile::TempDir::new().unwrap();
+    let deep = root.path().join("a").join("b");
+    create_scope(&setup, root.path(), "root value").await;
+    create_scope(&setup, &deep, "deep value").await;
+    dip::create(&application.db_pool, None, "global value", None)
+        .await
+        .unwrap();
+
+    let ctx = RuntimeDirContext::try_from(deep.as_path()).unwrap();
+    let chain = dir_context::get_scope_chain(&application.db_pool, &ctx, None)
+        .await
+        .unwrap();
+    let origins = chain.iter().map(|x| x.origin).collect::<Vec<_>>();
+    assert_eq!(
+        origins,
+        vec![
+            ScopeOrigin::Current,
+            ScopeOrigin::Parent,
+            ScopeOrigin::Global
+        ]
+    );
+
+    let rows = dip::get_inherited(&application.db_pool, &chain, dip::DipsFilter::new())
+        .await
+        .unwrap();
+    let values = rows.iter().map(|x| x.value.as_str()).collect::<Vec<_>>();
+    assert_eq!(values, vec!["deep value", "root value", "global value"]);
+}
+
+#[tokio::test]
+async fn scope_chain_respects_the_depth() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    let root = tempfile::TempDir::new().unwrap();
+    let deep = root.path().join("a");
+    create_scope(&setup, root.path(), "root value").await;
+    create_scope(&setup, &deep, "deep value").await;
+
+    let ctx = RuntimeDirContext::try_from(deep.as_path()).unwrap();
+    let chain = dir_context::get_scope_chain(&application.db_pool, &ctx, Some(0))
+        .await
+        .unwrap();
+
+    assert_eq!(chain.len(), 1);
+    assert_eq!(chain[0].origin, ScopeOrigin::Current);
+}
+
+#[tokio::test]
+async fn scope_chain_skips_sibling_with_common_prefix() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    let root = tempfile::TempDir::new().unwrap();
+    let api = root.path().join("api");
+    let api_v2 = root.path().join("api-v2");
+    create_scope(&setup, &api, "api value").await;
+    std::fs::create_dir_all(&api_v2).unwrap();
+
+    let ctx = RuntimeDirContext::try_from(api_v2.as_path()).unwrap();
+    let chain = dir_context::get_scope_chain(&application.db_pool, &ctx, None)
+        .await
+        .unwrap();
+
+    assert_eq!(chain.len(), 1);
+    assert_eq!(chain[0].origin, ScopeOrigin::Global);
+}
+
+async fn rendered(format: OutputFormat) -> String {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    dip::create(
+        &application.db_pool,
+        None,
+        "printf 'a\tb'\necho",
+        Some("two lines"),
+    )
+    .await
+    .unwrap();
+    let items = dip::get_all(&application.db_pool).await.unwrap();
+    get::render(&items, format)
+}
+
+#[tokio::test]
+async fn json_output_has_the_dip_fields() {
+    let out = rendered(OutputFormat::Json).await;
+
+    let items: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(items[0]["value"], "printf 'a\tb'\necho");
+    assert_eq!(items[0]["note"], "two lines");
+}
+
+#[tokio::test]
+async fn yaml_output_has_the_dip_fields() {
+    let out = rendered(OutputFormat::Yaml).await;
+
+    let items: serde_yaml::Value = serde_yaml::from_str(&out).unwrap();
+    assert_eq!(items[0]["value"].as_str(), Some("printf 'a\tb'\necho"));
+    assert_eq!(items[0]["note"].as_str(), Some("two lines"));
+}
+
+#[tokio::test]
+async fn tsv_output_escapes_the_tabs_and_new_lines() {
+    let out = rendered(OutputFormat::Tsv).await;
+
+    let lines = out.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("id\tvalue\tnote\t"));
+    let fields = lines[1].split('\t').collect::<Vec<_>>();
+    assert_eq!(fields.len(), 9);
+    assert_eq!(fields[1], "printf 'a\\tb'\\necho");
+    assert_eq!(fields[2], "two lines");
+}
+
+#[tokio::test]
+async fn null_output_keeps_the_value_as_is() {
+    let out = rendered(OutputFormat::Null).await;
+
+    assert_eq!(out, "printf 'a\tb'\necho\0");
+}
+
+#[tokio::test]
+async fn scope_chain_skips_the_siblings_matching_a_wildcard() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    let root = tempfile::TempDir::new().unwrap();
+    let root = root.path().canonicalize().unwrap();
+    create_scope(&setup, &root.join("a_b"), "sibling value").await;
+    create_scope(&setup, &root.join("A%"), "other value").await;
+    let deep = root.join("axb").join("d");
+    std::fs::create_dir_all(&deep).unwrap();
+
+    let ctx = RuntimeDirContext::try_from(deep.as_path()).unwrap();
+    let chain = dir_context::get_scope_chain(&application.db_pool, &ctx, None)
+        .await
+        .unwrap();
+
+    let origins = chain.iter().map(|x| x.origin).collect::<Vec<_>>();
+    assert_eq!(origins, vec![ScopeOrigin::Global]);
+}
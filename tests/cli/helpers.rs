@@ -1,35 +1,74 @@
 use dips::configuration::{Application, Environment, Settings};
 use dips::models::dir_context::RuntimeDirContext;
+use std::path::Path;
 
 #[derive(Debug)]
 pub struct TestApp {
-    temp_dir: tempfile::TempDir,
-    context_dir: RuntimeDirContext,
+    context_dir: tempfile::TempDir,
     application: Application,
 }
 
 impl TestApp {
     pub async fn setup() -> Self {
+        let context_dir = tempfile::TempDir::new().expect("Failed to create a temp directory.");
+        Self::build(context_dir).await
+    }
+
+    /// Like `setup`, with the context dir being a git repository with a
+    /// commit on the checked out branch and the other branches next to it.
+    pub async fn setup_in_repo(branch: &str, others: &[&str]) -> Self {
+        let context_dir = tempfile::TempDir::new().expect("Failed to create a temp directory.");
+        let repo =
+            git2::Repository::init(context_dir.path()).expect("Failed to init the repository.");
+        repo.set_head(&format!("refs/heads/{branch}"))
+            .expect("Failed to set the branch.");
+        let signature = git2::Signature::now("dips", "dips@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let commit = repo
+            .commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .expect("Failed to commit.");
+        let commit = repo.find_commit(commit).unwrap();
+        for name in others {
+            repo.branch(name, &commit, false)
+                .expect("Failed to create the branch.");
+        }
+        Self::build(context_dir).await
+    }
+
+    async fn build(context_dir: tempfile::TempDir) -> Self {
         let settings = {
             let mut s = Settings::build(&Environment::current());
             s.database.path = "sqlite::memory:".to_string();
+            s.dir = Some(context_dir.path().to_path_buf());
             s
         };
         let application = Application::build(settings)
             .await
             .expect("Failed to build the application.");
-        let temp_dir = tempfile::TempDir::new().expect("Failed to create a temp directory.");
-        let context_dir = RuntimeDirContext::try_from(temp_dir.path())
-            .expect("Failed to determine context from temp dir");
 
         TestApp {
             application,
             context_dir,
-            temp_dir,
         }
     }
 
     pub fn application(&self) -> &Application {
         &self.application
     }
+
+    /// Another application on the same database acting on the directory,
+    /// e.g. a subdirectory or another clone of the repository.
+    pub fn application_in(&self, dir: &Path) -> Application {
+        Application {
+            db_pool: self.application.db_pool.clone(),
+            context_dir: RuntimeDirContext::try_from(dir).expect("Failed to read the directory."),
+            settings: Settings::build(&Environment::current()),
+        }
+    }
+
+    /// The directory the application acts on, like `dips -C <dir>`.
+    pub fn context_dir(&self) -> &Path {
+        self.context_dir.path()
+    }
 }
@@ -0,0 +1,103 @@
+use crate::helpers::TestApp;
+use dips::commands::{
+    add::{self, AddOptions},
+    hook, shell_init,
+};
+use dips::models::dip;
+use dips::models::dir_context::DirContext;
+use std::time::{Duration, SystemTime};
+
+#[tokio::test]
+async fn summary_lists_the_first_values() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    for i in 0..7 {
+        add::add(
+            application,
+            &format!("cargo test {i}"),
+            None,
+            &[],
+            &AddOptions::new().with_global(true),
+        )
+        .await
+        .unwrap();
+    }
+    let items = dip::get_all(&application.db_pool).await.unwrap();
+    let now = chrono::Utc::now().naive_utc();
+    let scope = DirContext {
+        id: uuid::Uuid::new_v4(),
+        git_remote: None,
+        canonical_remote: None,
+        git_dir_name: None,
+        dir_path: "/work/dips".to_string(),
+        subpath: String::new(),
+        created_at: now,
+        updated_at: now,
+    };
+
+    let summary = hook::summary(&scope, &items);
+
+    let lines = summary.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines[0], "dips: 7 in /work/dips");
+    assert_eq!(lines[6], "  ... and 2 more, see `dips get`");
+}
+
+#[test]
+fn every_shell_script_calls_the_hook() {
+    for shell in [
+        shell_init::InitShell::Bash,
+        shell_init::InitShell::Zsh,
+        shell_init::InitShell::Fish,
+    ] {
+        assert!(shell_init::script(shell).contains("dips hook --session"));
+    }
+}
+
+#[test]
+fn summary_is_not_repeated_in_the_same_scope() {
+    let mut state = hook::HookState::default();
+    let scope = uuid::Uuid::new_v4();
+
+    assert!(state.enter(Some(scope), 0));
+    state.mark_shown(scope, 0);
+    assert!(!state.enter(Some(scope), 10));
+    assert!(!state.enter(None, 20));
+    assert!(!state.enter(None, 30));
+}
+
+#[test]
+fn summary_is_throttled_when_coming_back() {
+    let mut state = hook::HookState::default();
+    let (first, second) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+
+    assert!(state.enter(Some(first), 0));
+    state.mark_shown(first, 0);
+    assert!(state.enter(Some(second), 60));
+    state.mark_shown(second, 60);
+    assert!(!state.enter(Some(first), 120));
+    assert!(!state.enter(Some(second), 180));
+    assert!(state.enter(Some(first), 600));
+}
+
+#[test]
+fn stale_session_states_are_pruned() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let old = dir.path().join("hook-1.json");
+    let fresh = dir.path().join("hook-2.json");
+    let other = dir.path().join("other.json");
+    for path in [&old, &fresh, &other] {
+        std::fs::write(path, "{}").unwrap();
+    }
+    let long_ago = SystemTime::now() - Duration::from_secs(3600);
+    for path in [&old, &other] {
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(long_ago).unwrap();
+    }
+
+    hook::prune_states(dir.path(), Duration::from_secs(60));
+
+    assert!(!old.exists());
+    assert!(fresh.exists());
+    assert!(other.exists());
+}
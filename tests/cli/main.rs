@@ -1,2 +1,21 @@
-mod helpers;
 mod add;
+mod backup;
+mod branch;
+mod clipboard;
+mod completions;
+mod config;
+mod dir_context;
+mod edit;
+mod get;
+mod helpers;
+mod hook;
+mod note;
+mod repo_file;
+mod rm;
+mod run;
+mod scope;
+mod search;
+mod tag;
+mod tag_expr;
+mod tags;
+mod template;
@@ -0,0 +1,61 @@
+use crate::helpers::TestApp;
+use dips::commands::{
+    add::{self, AddOptions},
+    note,
+};
+use dips::models::dip;
+
+#[tokio::test]
+async fn adding_value_with_note_stores_note() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "make release",
+        Some("bumps the version"),
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows[0].note.as_deref(), Some("bumps the version"));
+}
+
+#[tokio::test]
+async fn setting_empty_note_removes_it() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "make release",
+        Some("old"),
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+
+    let edited = note::normalize("\n  \n");
+    dip::update(
+        &application.db_pool,
+        &rows[0].id,
+        None,
+        Some(edited.as_deref()),
+    )
+    .await
+    .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows[0].note, None);
+}
+
+#[test]
+fn note_keeps_inner_lines_without_trailing_whitespace() {
+    assert_eq!(
+        note::normalize("first line\n\nsecond line\n"),
+        Some("first line\n\nsecond line".to_string())
+    );
+}
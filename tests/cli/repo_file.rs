@@ -0,0 +1,128 @@
+use crate::helpers::TestApp;
+use dips::commands::{import, run};
+use dips::models::backup::ImportMode;
+use dips::models::dip;
+use dips::models::dir_context::{self, ScopeOrigin};
+use dips::models::repo_file::{self, RepoDip, RepoFile};
+
+fn sample() -> RepoFile {
+    let mut file = RepoFile::default();
+    file.push(RepoDip {
+        value: "make setup".to_string(),
+        note: Some("first time only".to_string()),
+        tags: vec!["onboarding".to_string()],
+    });
+    file.push(RepoDip {
+        value: "psql -h localhost".to_string(),
+        note: None,
+        tags: vec![],
+    });
+    file
+}
+
+#[test]
+fn repo_file_roundtrips_through_yaml_and_toml() {
+    let dir = tempfile::TempDir::new().unwrap();
+    for name in [".dips.yaml", ".dips.toml"] {
+        let path = dir.path().join(name);
+        repo_file::write(&path, &sample()).unwrap();
+
+        let file = repo_file::read(&path).unwrap();
+        assert_eq!(file.dips, sample().dips);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn repo_file_is_found_in_the_git_root() {
+    let dir = tempfile::TempDir::new().unwrap();
+    assert!(repo_file::find(dir.path()).is_none());
+    assert_eq!(
+        repo_file::path_or_default(dir.path()),
+        dir.path().join(".dips.yaml")
+    );
+
+    let path = dir.path().join(".dips.toml");
+    repo_file::write(&path, &sample()).unwrap();
+    assert_eq!(repo_file::find(dir.path()), Some(path.clone()));
+    assert_eq!(repo_file::path_or_default(dir.path()), path);
+}
+
+#[test]
+fn repo_file_rows_are_marked_and_stable() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join(".dips.yaml");
+    repo_file::write(&path, &sample()).unwrap();
+
+    let rows = repo_file::get_rows(Some(dir.path())).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|x| x.origin == Some(ScopeOrigin::RepoFile)));
+    assert_eq!(rows[0].tags[0].name, "onboarding");
+
+    let again = repo_file::get_rows(Some(dir.path())).unwrap();
+    assert_eq!(rows[0].id, again[0].id);
+    assert_ne!(rows[0].id, rows[1].id);
+}
+
+#[test]
+fn repo_file_rows_run_in_the_repository_root() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join(".dips.yaml");
+    repo_file::write(&path, &sample()).unwrap();
+
+    let rows = repo_file::get_rows(Some(dir.path())).unwrap();
+    let row_dir = rows[0].dir_path.as_deref().unwrap();
+    assert_eq!(row_dir, dir.path().display().to_string());
+
+    let status = run::run_value("test -f .dips.yaml", row_dir.as_ref()).unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn repo_file_skips_duplicate_values() {
+    let mut file = sample();
+    let added = file.push(RepoDip {
+        value: "make setup".to_string(),
+        note: None,
+        tags: vec![],
+    });
+
+    assert!(!added);
+    assert_eq!(file.dips.len(), 2);
+}
+
+#[tokio::test]
+async fn importing_repo_file_adds_repeated_values_once() {
+    let setup = TestApp::setup_in_repo("main", &[]).await;
+    let application = setup.application();
+    let content = "dips:\n  - value: make setup\n  - value: make setup\n    note: again\n";
+    std::fs::write(setup.context_dir().join(".dips.yaml"), content).unwrap();
+
+    import::exec(application, None, ImportMode::Merge, false, true)
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, "make setup");
+}
+
+#[tokio::test]
+async fn importing_repo_file_from_a_subdirectory_uses_the_repository_scope() {
+    let setup = TestApp::setup_in_repo("main", &[]).await;
+    let subdir = setup.context_dir().join("crates/cli");
+    std::fs::create_dir_all(&subdir).unwrap();
+    let content = "dips:\n  - value: make setup\n    tags: [db, db, ' db ']\n";
+    std::fs::write(setup.context_dir().join(".dips.yaml"), content).unwrap();
+    let nested = setup.application_in(&subdir);
+
+    import::exec(&nested, None, ImportMode::Merge, false, true)
+        .await
+        .unwrap();
+
+    let scopes = dir_context::get_usage(&nested.db_pool).await.unwrap();
+    assert_eq!(scopes.len(), 1);
+    assert_eq!(scopes[0].scope.subpath, "");
+    let rows = dip::get_all(&nested.db_pool).await.unwrap();
+    assert_eq!(rows[0].tags.len(), 1);
+}
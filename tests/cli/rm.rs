@@ -0,0 +1,128 @@
+use crate::helpers::TestApp;
+use dips::commands::{
+    add::{self, AddOptions},
+    rm,
+};
+use dips::error::DipsError;
+use dips::models::dip;
+use fake::faker::lorem::en::Word;
+use fake::Fake;
+use std::io::IsTerminal;
+
+#[tokio::test]
+async fn removing_by_exact_value_removes_the_dip() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    let input: String = Word().fake();
+    add::add(application, &input, None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    rm::exec(application, &input, false).await.unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert!(rows.is_empty());
+}
+
+#[tokio::test]
+async fn removing_by_id_prefix_removes_the_dip() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(application, "first value", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+    add::add(application, "second value", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    let target = rows.iter().find(|x| x.value == "first value").unwrap();
+    let prefix = target.id.to_string()[..8].to_string();
+
+    rm::exec(application, &prefix, false).await.unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, "second value");
+}
+
+#[tokio::test]
+async fn removing_by_fuzzy_query_removes_the_dip() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "docker compose up",
+        None,
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    rm::exec(application, "dcup", true).await.unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, "cargo test");
+}
+
+#[tokio::test]
+async fn removing_with_ambiguous_query_keeps_the_dips() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(application, "cargo build", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    let result = rm::exec(application, "cargo", true).await;
+
+    assert!(matches!(result, Err(DipsError::AmbiguousSelector(_))));
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows.len(), 2);
+}
+
+#[tokio::test]
+async fn removing_unknown_value_fails() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    let result = rm::exec(application, "kubectl", false).await;
+
+    assert!(matches!(result, Err(DipsError::DipNotFound(_))));
+    assert_eq!(result.unwrap_err().exit_code(), 9);
+}
+
+#[tokio::test]
+async fn removing_by_fuzzy_query_needs_a_confirmation() {
+    // With a terminal the test would wait for the answer.
+    if std::io::stdin().is_terminal() {
+        return;
+    }
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "docker compose up",
+        None,
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    let result = rm::exec(application, "dcup", false).await;
+
+    assert!(matches!(result, Err(DipsError::Validation(_))));
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+}
@@ -0,0 +1,100 @@
+use crate::helpers::TestApp;
+use dips::commands::{
+    add::{self, AddOptions},
+    run,
+};
+use dips::models::dip;
+use dips::models::dir_context::{self, ScopeOrigin};
+use std::path::{Path, PathBuf};
+
+/// A clone of the same repository in the directory.
+fn clone_api(dir: &Path) -> PathBuf {
+    std::fs::create_dir_all(dir).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    let repo = git2::Repository::init(&dir).unwrap();
+    repo.remote("origin", "git@github.com:o/api.git").unwrap();
+    dir
+}
+
+#[test]
+fn destructive_commands_are_dangerous() {
+    assert!(run::is_dangerous("sudo apt upgrade"));
+    assert!(run::is_dangerous("rm  -Rf target"));
+    assert!(run::is_dangerous("git push --force-with-lease"));
+    assert!(!run::is_dangerous("cargo test"));
+    assert!(!run::is_dangerous("docker compose up -d"));
+}
+
+#[tokio::test]
+async fn running_dip_returns_its_exit_code() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "exit 3",
+        None,
+        &[],
+        &AddOptions::new().with_global(true),
+    )
+    .await
+    .unwrap();
+
+    let status = run::exec(application, "exit 3", false).await.unwrap();
+
+    assert_eq!(status.code(), Some(3));
+}
+
+#[test]
+fn exit_code_is_the_one_of_the_command() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let status = run::run_value("exit 3", dir.path()).unwrap();
+    assert_eq!(run::exit_code(&status), 3);
+
+    let status = run::run_value("kill -TERM $$", dir.path()).unwrap();
+    assert_eq!(run::exit_code(&status), 128 + 15);
+}
+
+#[tokio::test]
+async fn shared_scope_runs_in_the_current_clone() {
+    let setup = TestApp::setup().await;
+    let first = clone_api(&setup.context_dir().join("a/api"));
+    let second = clone_api(&setup.context_dir().join("b/api"));
+    let added = setup.application_in(&first);
+    add::add(&added, "pwd > ran-here", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&first).unwrap();
+
+    let application = setup.application_in(&second);
+    let chain = dir_context::get_scope_chain(&application.db_pool, &application.context_dir, None)
+        .await
+        .unwrap();
+    let status = run::exec(&application, "pwd > ran-here", false)
+        .await
+        .unwrap();
+
+    assert_eq!(chain[0].origin, ScopeOrigin::Current);
+    assert!(status.success());
+    assert!(second.join("ran-here").exists());
+}
+
+#[tokio::test]
+async fn unrelated_scope_runs_in_the_context_dir() {
+    let setup = TestApp::setup().await;
+    let other = tempfile::TempDir::new().unwrap();
+    let added = setup.application_in(other.path());
+    add::add(&added, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+    let item = &dip::get_all(&added.db_pool).await.unwrap()[0];
+    let scope = dir_context::get_closest(&added.db_pool, &added.context_dir)
+        .await
+        .unwrap();
+
+    let application = setup.application();
+    let inside = run::run_dir(&added.context_dir, item, scope.as_ref());
+    let elsewhere = run::run_dir(&application.context_dir, item, scope.as_ref());
+
+    assert_eq!(inside, added.context_dir.dir());
+    assert_eq!(elsewhere, application.context_dir.dir());
+}
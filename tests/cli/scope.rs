@@ -0,0 +1,234 @@
+use crate::helpers::TestApp;
+use dips::commands::add::{self, AddOptions};
+use dips::commands::scope::{self, RemoveMode};
+use dips::error::DipsError;
+use dips::models::{dip, dir_context};
+
+async fn create_scope(setup: &TestApp, path: &str) {
+    let application = setup.application();
+    let mut tx = application.db_pool.begin().await.unwrap();
+    dir_context::db_find_or_create(&mut tx, path, "", None, None, false)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn moving_scope_moves_the_nested_scopes() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    create_scope(&setup, "/old/repo").await;
+    create_scope(&setup, "/old/repo/crates/cli").await;
+    create_scope(&setup, "/old/repository").await;
+
+    scope::mv(application, "/old/repo".as_ref(), "/new/repo".as_ref())
+        .await
+        .unwrap();
+
+    let paths = dir_context::get_usage(&application.db_pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|x| x.scope.dir_path)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec!["/new/repo", "/new/repo/crates/cli", "/old/repository"]
+    );
+}
+
+#[tokio::test]
+async fn moving_scope_skips_the_siblings_matching_a_wildcard() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    create_scope(&setup, "/old/re_o").await;
+    create_scope(&setup, "/old/repo/crates").await;
+    create_scope(&setup, "/old/RE_O/crates").await;
+
+    scope::mv(application, "/old/re_o".as_ref(), "/new/re_o".as_ref())
+        .await
+        .unwrap();
+
+    let paths = dir_context::get_usage(&application.db_pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|x| x.scope.dir_path)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec!["/new/re_o", "/old/RE_O/crates", "/old/repo/crates"]
+    );
+}
+
+#[tokio::test]
+async fn moving_scope_onto_a_nested_scope_fails() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    create_scope(&setup, "/old/repo").await;
+    create_scope(&setup, "/old/repo/crates/cli").await;
+    create_scope(&setup, "/new/repo/crates/cli").await;
+
+    let result = scope::mv(application, "/old/repo".as_ref(), "/new/repo".as_ref()).await;
+
+    assert!(
+        matches!(result, Err(DipsError::Validation(ref x)) if x.contains("/new/repo/crates/cli"))
+    );
+    let scope = dir_context::find_by_path(&application.db_pool, "/old/repo/crates/cli")
+        .await
+        .unwrap();
+    assert!(scope.is_some());
+}
+
+#[tokio::test]
+async fn removing_scope_with_dips_needs_a_mode() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    let result = scope::rm(application, setup.context_dir(), RemoveMode::Empty).await;
+
+    assert!(matches!(result, Err(DipsError::Validation(_))));
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[tokio::test]
+async fn removing_scope_can_keep_the_dips_as_global() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    scope::rm(application, setup.context_dir(), RemoveMode::ReassignGlobal)
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].dir_context_id, None);
+    let scopes = dir_context::get_usage(&application.db_pool).await.unwrap();
+    assert!(scopes.is_empty());
+}
+
+#[tokio::test]
+async fn removing_scope_with_cascade_removes_the_dips() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "cargo test",
+        None,
+        &["rust".to_string()],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    add::add(
+        application,
+        "make",
+        None,
+        &[],
+        &AddOptions::new().with_global(true),
+    )
+    .await
+    .unwrap();
+
+    scope::rm(application, setup.context_dir(), RemoveMode::Cascade)
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, "make");
+}
+
+#[tokio::test]
+async fn pruning_keeps_existing_dirs_and_scopes_with_dips() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+    let existing = setup.context_dir().parent().unwrap().display().to_string();
+    create_scope(&setup, &existing).await;
+    create_scope(&setup, "/surely/gone/by/now").await;
+
+    scope::prune(application).await.unwrap();
+
+    let scopes = dir_context::get_usage(&application.db_pool).await.unwrap();
+    assert_eq!(scopes.len(), 2);
+    assert!(scopes
+        .iter()
+        .all(|x| x.scope.dir_path != "/surely/gone/by/now"));
+}
+
+#[tokio::test]
+async fn removing_scope_with_cascade_removes_the_archived_dips() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+    let item = &dip::get_all(&application.db_pool).await.unwrap()[0];
+    let mut tx = application.db_pool.begin().await.unwrap();
+    dip::archive(&mut tx, &item.id).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let result = scope::rm(application, setup.context_dir(), RemoveMode::Empty).await;
+    assert!(matches!(result, Err(DipsError::Validation(_))));
+
+    scope::rm(application, setup.context_dir(), RemoveMode::Cascade)
+        .await
+        .unwrap();
+
+    let scopes = dir_context::get_usage(&application.db_pool).await.unwrap();
+    assert!(scopes.is_empty());
+}
+
+#[tokio::test]
+async fn moving_scope_updates_the_git_details_of_every_moved_scope() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    let root = setup.context_dir().canonicalize().unwrap();
+    let repo = git2::Repository::init(&root).unwrap();
+    repo.remote("origin", "git@github.com:owner/new.git")
+        .unwrap();
+    std::fs::create_dir_all(root.join("crates/cli")).unwrap();
+    let mut tx = application.db_pool.begin().await.unwrap();
+    for (path, subpath) in [("/old/repo", ""), ("/old/repo/crates/cli", "crates/cli")] {
+        let remote = Some("git@github.com:owner/old.git".to_string());
+        dir_context::db_create(&mut tx, path, subpath, Some("repo".to_string()), remote)
+            .await
+            .unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    scope::mv(application, "/old/repo".as_ref(), &root)
+        .await
+        .unwrap();
+
+    let nested = root.join("crates/cli").display().to_string();
+    let nested = dir_context::find_by_path(&application.db_pool, &nested)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        nested.canonical_remote.as_deref(),
+        Some("github.com/owner/new")
+    );
+    assert_eq!(nested.subpath, "crates/cli");
+
+    scope::mv(application, &root, "/gone/repo".as_ref())
+        .await
+        .unwrap();
+
+    let scopes = dir_context::get_usage(&application.db_pool).await.unwrap();
+    assert_eq!(scopes.len(), 2);
+    assert!(scopes
+        .iter()
+        .all(|x| x.scope.canonical_remote.is_none() && x.scope.subpath.is_empty()));
+}
@@ -0,0 +1,134 @@
+use crate::helpers::TestApp;
+use dips::commands::{
+    add::{self, AddOptions},
+    edit, rm, select,
+};
+use dips::models::dip::{self, DipsFilter};
+
+#[tokio::test]
+async fn search_matches_value_prefix() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "docker compose up",
+        None,
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    add::add(application, "cargo test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+
+    let filter = DipsFilter::new().with_any_scope().with_search("dock");
+    let rows = dip::get_filtered(&application.db_pool, filter)
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, "docker compose up");
+    assert!(rows[0].snippet.is_some());
+}
+
+#[tokio::test]
+async fn search_matches_notes_and_tags() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "cargo test",
+        None,
+        &["rust".to_string()],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    add::add(application, "npm test", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+    edit::exec(application, "npm test", None, Some("needs node"))
+        .await
+        .unwrap();
+
+    let filter = DipsFilter::new().with_any_scope().with_search("rust");
+    let rows = dip::get_filtered(&application.db_pool, filter)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, "cargo test");
+
+    let filter = DipsFilter::new().with_any_scope().with_search("node");
+    let rows = dip::get_filtered(&application.db_pool, filter)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, "npm test");
+}
+
+#[tokio::test]
+async fn search_ranks_value_matches_first() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(application, "make release", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+    add::add(application, "deploy staging", None, &[], &AddOptions::new())
+        .await
+        .unwrap();
+    edit::exec(application, "make release", None, Some("before deploy"))
+        .await
+        .unwrap();
+
+    let filter = DipsFilter::new().with_any_scope().with_search("deploy");
+    let rows = dip::get_filtered(&application.db_pool, filter)
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].value, "deploy staging");
+    assert_eq!(rows[1].value, "make release");
+}
+
+#[tokio::test]
+async fn search_does_not_match_removed_dips() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "docker compose up",
+        None,
+        &[],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    rm::exec(application, "docker compose up", false)
+        .await
+        .unwrap();
+
+    let filter = DipsFilter::new().with_any_scope().with_search("docker");
+    let rows = dip::get_filtered(&application.db_pool, filter)
+        .await
+        .unwrap();
+
+    assert!(rows.is_empty());
+}
+
+#[tokio::test]
+async fn fuzzy_filter_ranks_the_best_match_first() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    for value in ["docker compose ps", "docker ps", "cargo test"] {
+        add::add(application, value, None, &[], &AddOptions::new())
+            .await
+            .unwrap();
+    }
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+
+    let matches = select::fuzzy_matches(rows, "dkrps");
+
+    let values = matches.iter().map(|x| x.value.as_str()).collect::<Vec<_>>();
+    assert_eq!(values, vec!["docker ps", "docker compose ps"]);
+}
@@ -0,0 +1,60 @@
+use crate::helpers::TestApp;
+use dips::commands::{
+    add::{self, AddOptions},
+    tag,
+};
+use dips::models::dip;
+
+fn names(row: &dip::DipRowFull) -> Vec<&str> {
+    let mut names = row.tags.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn tagging_adds_and_removes_tags() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "cargo test",
+        None,
+        &["rust".to_string()],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    let changes = ["+ci", "-rust", "local"].map(String::from);
+    tag::exec(application, "cargo test", &changes)
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(names(&rows[0]), vec!["ci", "local"]);
+}
+
+#[tokio::test]
+async fn tagging_with_existing_tag_is_ignored() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "cargo test",
+        None,
+        &["rust".to_string()],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    tag::exec(application, "cargo test", &["+rust".to_string()])
+        .await
+        .unwrap();
+    tag::exec(application, "cargo test", &["-missing".to_string()])
+        .await
+        .unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(names(&rows[0]), vec!["rust"]);
+}
@@ -0,0 +1,102 @@
+use crate::helpers::TestApp;
+use dips::commands::add::{self, AddOptions};
+use dips::models::dip::{self, DipsFilter};
+use dips::models::tag_expr::{TagExpr, TagExprError};
+
+fn tags(values: &[&str]) -> Vec<String> {
+    values.iter().map(|x| x.to_string()).collect()
+}
+
+#[test]
+fn tag_expr_parses_operators_and_precedence() {
+    let expr: TagExpr = "db and (prod or staging)".parse().unwrap();
+    assert_eq!(expr.to_string(), "db and (prod or staging)");
+
+    let expr: TagExpr = "deploy !legacy | ci,local".parse().unwrap();
+    assert_eq!(expr.to_string(), "deploy and !legacy or ci and local");
+    assert!(expr.matches(&["deploy"]));
+    assert!(!expr.matches(&["deploy", "legacy"]));
+    assert!(expr.matches(&["ci", "local", "legacy"]));
+}
+
+#[test]
+fn tag_expr_reports_invalid_input() {
+    assert_eq!("".parse::<TagExpr>(), Err(TagExprError::Empty));
+    assert_eq!(
+        "db and".parse::<TagExpr>(),
+        Err(TagExprError::UnexpectedEnd)
+    );
+    assert_eq!("(db".parse::<TagExpr>(), Err(TagExprError::UnexpectedEnd));
+    assert_eq!(
+        "db)".parse::<TagExpr>(),
+        Err(TagExprError::Unexpected(")".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn tag_expr_filters_the_dips_in_sql() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "deploy prod",
+        None,
+        &tags(&["deploy", "prod"]),
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    add::add(
+        application,
+        "deploy old",
+        None,
+        &tags(&["deploy", "legacy"]),
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    add::add(
+        application,
+        "psql staging",
+        None,
+        &tags(&["db", "staging"]),
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    add::add(
+        application,
+        "psql dev",
+        None,
+        &tags(&["db"]),
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    let query = |exprs: &[&str]| {
+        let exprs = exprs.iter().map(|x| x.parse().unwrap()).collect();
+        let filter = DipsFilter::new()
+            .with_any_scope()
+            .with_tags(TagExpr::all(exprs));
+        let pool = application.db_pool.clone();
+        async move {
+            let mut values = dip::get_filtered(&pool, filter)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|x| x.value)
+                .collect::<Vec<_>>();
+            values.sort();
+            values
+        }
+    };
+
+    assert_eq!(query(&["deploy", "!legacy"]).await, vec!["deploy prod"]);
+    assert_eq!(
+        query(&["db and (prod or staging)"]).await,
+        vec!["psql staging"]
+    );
+    assert_eq!(query(&["prod or staging"]).await.len(), 2);
+    assert!(query(&["missing"]).await.is_empty());
+}
@@ -0,0 +1,168 @@
+use crate::helpers::TestApp;
+use dips::commands::{
+    add::{self, AddOptions},
+    tags,
+};
+use dips::error::DipsError;
+use dips::models::{dip, tag};
+
+fn tag_names(rows: &[dip::DipRowFull], value: &str) -> Vec<String> {
+    let row = rows.iter().find(|x| x.value == value).unwrap();
+    let mut names = row
+        .tags
+        .iter()
+        .map(|x| x.name.to_owned())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn tags_usage_counts_dips_per_scope() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "psql",
+        None,
+        &["db".to_string()],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    add::add(
+        application,
+        "mysql",
+        None,
+        &["db".to_string()],
+        &AddOptions::new().with_global(true),
+    )
+    .await
+    .unwrap();
+    add::add(
+        application,
+        "redis-cli",
+        None,
+        &["db".to_string()],
+        &AddOptions::new().with_global(true),
+    )
+    .await
+    .unwrap();
+
+    let usage = tag::get_usage(&application.db_pool).await.unwrap();
+    let counts = usage
+        .iter()
+        .map(|x| (x.name.as_str(), x.global, x.count))
+        .collect::<Vec<_>>();
+    assert_eq!(counts, vec![("db", false, 1), ("db", true, 2)]);
+}
+
+#[tokio::test]
+async fn tags_merge_resolves_collisions() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    let both = ["db".to_string(), "database".to_string()];
+    add::add(application, "psql", None, &both, &AddOptions::new())
+        .await
+        .unwrap();
+    add::add(
+        application,
+        "mysql",
+        None,
+        &["database".to_string()],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    tags::merge(application, "database", "db").await.unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(tag_names(&rows, "psql"), vec!["db"]);
+    assert_eq!(tag_names(&rows, "mysql"), vec!["db"]);
+    let usage = tag::get_usage(&application.db_pool).await.unwrap();
+    assert_eq!(usage.len(), 1);
+}
+
+#[tokio::test]
+async fn tags_rename_keeps_existing_name_untouched() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "psql",
+        None,
+        &["db".to_string()],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    add::add(
+        application,
+        "mysql",
+        None,
+        &["sql".to_string()],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    let result = tags::rename(application, "db", "sql").await;
+    tags::rename(application, "db", "postgres").await.unwrap();
+
+    assert!(matches!(result, Err(DipsError::Validation(_))));
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(tag_names(&rows, "psql"), vec!["postgres"]);
+    assert_eq!(tag_names(&rows, "mysql"), vec!["sql"]);
+}
+
+#[tokio::test]
+async fn tags_rename_needs_a_usable_name() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "psql",
+        None,
+        &["db".to_string()],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    for name in ["", "  ", "db,sql"] {
+        let result = tags::rename(application, "db", name).await;
+        assert!(matches!(result, Err(DipsError::Validation(_))), "{name:?}");
+        let result = tags::merge(application, "db", name).await;
+        assert!(matches!(result, Err(DipsError::Validation(_))), "{name:?}");
+    }
+    tags::rename(application, "db", " sql ").await.unwrap();
+
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(tag_names(&rows, "psql"), vec!["sql"]);
+}
+
+#[tokio::test]
+async fn tags_prune_removes_only_unused_tags() {
+    let setup = TestApp::setup().await;
+    let application = setup.application();
+    add::add(
+        application,
+        "psql",
+        None,
+        &["db".to_string(), "old".to_string()],
+        &AddOptions::new(),
+    )
+    .await
+    .unwrap();
+    tags::rm(application, "db").await.unwrap();
+
+    let mut tx = application.db_pool.begin().await.unwrap();
+    tag::get_or_create(&mut tx, "unused").await.unwrap();
+    let pruned = tag::prune(&mut tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!(pruned, vec!["unused"]);
+    let rows = dip::get_all(&application.db_pool).await.unwrap();
+    assert_eq!(tag_names(&rows, "psql"), vec!["old"]);
+}
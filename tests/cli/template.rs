@@ -0,0 +1,63 @@
+use crate::helpers::TestApp;
+use dips::models::template::{self, Placeholder};
+use std::collections::HashMap;
+
+#[test]
+fn placeholders_are_listed_once_with_defaults() {
+    let items = template::placeholders("kubectl logs -n {{namespace:staging}} {{pod}} {{ pod }}");
+    assert_eq!(
+        items,
+        vec![
+            Placeholder {
+                name: "namespace".to_string(),
+                default: Some("staging".to_string()),
+            },
+            Placeholder {
+                name: "pod".to_string(),
+                default: None,
+            },
+        ]
+    );
+    assert!(!template::is_template("echo {{}} {{not a name}}"));
+}
+
+#[test]
+fn go_templates_are_not_placeholders() {
+    let value = "docker ps --format '{{.Names}} {{ json .Ports }}' {{ container-id }}";
+    let items = template::placeholders(value);
+    assert_eq!(
+        items,
+        vec![Placeholder {
+            name: "container-id".to_string(),
+            default: None,
+        }]
+    );
+    assert!(!template::is_template("docker ps --format '{{.Names}}'"));
+
+    let values = HashMap::from([("container-id".to_string(), "api".to_string())]);
+    assert_eq!(
+        template::render(value, &values),
+        "docker ps --format '{{.Names}} {{ json .Ports }}' api"
+    );
+}
+
+#[test]
+fn rendering_falls_back_to_defaults() {
+    let values = HashMap::from([("pod".to_string(), "api-1".to_string())]);
+    let value = template::render("kubectl logs -n {{namespace:staging}} {{pod}}", &values);
+    assert_eq!(value, "kubectl logs -n staging api-1");
+}
+
+#[tokio::test]
+async fn last_values_are_remembered_per_scope() {
+    let setup = TestApp::setup().await;
+    let pool = &setup.application().db_pool;
+    let first = HashMap::from([("pod".to_string(), "api-1".to_string())]);
+    let second = HashMap::from([("pod".to_string(), "api-2".to_string())]);
+
+    template::save_values(pool, None, &first).await.unwrap();
+    template::save_values(pool, None, &second).await.unwrap();
+
+    let last = template::get_last_values(pool, None).await.unwrap();
+    assert_eq!(last, second);
+}